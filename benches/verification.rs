@@ -0,0 +1,182 @@
+// Measures signature verification with cargo bench. Since the verification of
+// an invalid signature stops at the first failed check, valid and invalid
+// signatures are measured separately.
+#![allow(dead_code, unused_imports)]
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use crypto::{verify_batch, Keypair, Signature, XOnlyPublicKey};
+
+// The multi-scalar multiplication algorithms are not part of the public API,
+// so their modules are included directly as in the build script. Without the
+// test harness their tests are not compiled, which leaves some of the items
+// and imports they use unused.
+#[path = "../src/field.rs"]
+mod field;
+#[path = "../src/field_inversion.rs"]
+mod field_inversion;
+#[path = "../src/field_multiplication.rs"]
+mod field_multiplication;
+#[path = "../src/group.rs"]
+mod group;
+#[path = "../src/group_multiplication.rs"]
+mod group_multiplication;
+#[path = "../src/hash.rs"]
+mod hash;
+#[cfg(target_arch = "x86_64")]
+#[path = "../src/hash_x86_64.rs"]
+mod hash_x86_64;
+#[path = "../src/scalar.rs"]
+mod scalar;
+#[path = "../src/schnorr.rs"]
+mod schnorr;
+
+const SAMPLES: usize = 50;
+const ITERATIONS: usize = 20;
+
+// Reports the fastest and the median sample, since the slow samples are
+// dominated by interrupts and other processes rather than by the code.
+fn bench<F: FnMut()>(name: &str, mut f: F) {
+    // warm up the caches and the branch predictor
+    for _ in 0..ITERATIONS {
+        f();
+    }
+
+    let mut samples: Vec<f64> = (0..SAMPLES)
+        .map(|_| {
+            let start = Instant::now();
+
+            for _ in 0..ITERATIONS {
+                f();
+            }
+
+            start.elapsed().as_nanos() as f64 / ITERATIONS as f64 / 1000.0
+        })
+        .collect();
+
+    samples.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    println!(
+        "{:<24} min {:>10.2} us/iter, median {:>10.2} us/iter",
+        name,
+        samples[0],
+        samples[SAMPLES / 2]
+    );
+}
+
+// Distinct points and scalars without any structure the algorithms could
+// benefit from.
+fn terms(n: usize) -> Vec<(group::AffinePoint, [u8; 32])> {
+    let mut point = group::GENERATOR;
+    let mut scalar = [0xA5u8; 32];
+
+    (0..n)
+        .map(|i| {
+            point = point.double().add(group::GENERATOR);
+            scalar[i % 32] ^= scalar[(7 * i + 3) % 32].rotate_left(3) ^ i as u8;
+
+            (point.to_affine(), scalar)
+        })
+        .collect()
+}
+
+// The check of the nonce point before check_nonce_point, which converted R to
+// affine coordinates before comparing x and the parity of y.
+fn check_affine(h: group::Point, r: [u8; 32]) -> Result<(), schnorr::VerificationError> {
+    if h.is_neutral() {
+        return Err(schnorr::VerificationError::IsNeutral);
+    }
+
+    let h = h.to_affine();
+
+    if !h.y.is_even() {
+        return Err(schnorr::VerificationError::IsOdd);
+    }
+
+    if h.x.encode() != r {
+        return Err(schnorr::VerificationError::NotEqual);
+    }
+
+    Ok(())
+}
+
+fn main() {
+    let keypairs: Vec<Keypair> = (1..=64u8)
+        .map(|i| Keypair::from_bytes([i; 32]).unwrap())
+        .collect();
+
+    let signatures: Vec<Signature> = keypairs
+        .iter()
+        .map(|k| k.sign(b"message", [0; 32]))
+        .collect();
+
+    let public_key = keypairs[0].public_key();
+    let signature = signatures[0];
+
+    bench("sign", || {
+        black_box(keypairs[0].sign(black_box(b"message"), [0; 32]));
+    });
+
+    bench("verify valid", || {
+        assert!(public_key.verify(black_box(b"message"), signature).is_ok());
+    });
+
+    bench("verify invalid", || {
+        assert!(public_key.verify(black_box(b"massage"), signature).is_err());
+    });
+
+    let batch: Vec<(XOnlyPublicKey, &[u8], Signature)> = keypairs
+        .iter()
+        .zip(&signatures)
+        .map(|(k, s)| (k.public_key(), &b"message"[..], *s))
+        .collect();
+
+    bench("verify batch of 64", || {
+        assert!(verify_batch(black_box(&batch)).is_ok());
+    });
+
+    // a point with an even y coordinate and z != 1
+    let mut h = group::GENERATOR.double();
+    if !h.affine_y().is_even() {
+        h = h.add(group::GENERATOR);
+    }
+
+    let valid = h.affine_x().encode();
+    let invalid = group::GENERATOR.affine_x().encode();
+
+    assert!(schnorr::check_nonce_point(h, valid).is_ok());
+    assert!(check_affine(h, valid).is_ok());
+
+    bench("check affine valid", || {
+        assert!(check_affine(black_box(h), valid).is_ok());
+    });
+
+    bench("check projective valid", || {
+        assert!(schnorr::check_nonce_point(black_box(h), valid).is_ok());
+    });
+
+    bench("check affine invalid", || {
+        assert!(check_affine(black_box(h), invalid).is_err());
+    });
+
+    bench("check projective invalid", || {
+        assert!(schnorr::check_nonce_point(black_box(h), invalid).is_err());
+    });
+
+    // The crossover between the two algorithms determines PIPPENGER_THRESHOLD.
+    for n in [64, 128, 160, 176, 192, 208, 224, 256] {
+        let terms = terms(n);
+
+        bench(&format!("strauss {n} terms"), || {
+            black_box(group_multiplication::strauss(
+                black_box(&terms),
+                group_multiplication::WNAF_WIDTH,
+            ));
+        });
+
+        bench(&format!("pippenger {n} terms"), || {
+            black_box(group_multiplication::pippenger(black_box(&terms)));
+        });
+    }
+}
@@ -0,0 +1,79 @@
+// Generates the table of precomputed multiples of the generator used for
+// fixed base multiplication, using the crates own field and group arithmetic.
+#![allow(dead_code)]
+
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+#[path = "src/field.rs"]
+mod field;
+#[path = "src/field_inversion.rs"]
+mod field_inversion;
+#[path = "src/field_multiplication.rs"]
+mod field_multiplication;
+#[path = "src/group.rs"]
+mod group;
+
+// The scalar is recoded into 65 signed digits in [-8, 8), window i holds the
+// points j * 16^i * G for j in 1..=8.
+const WINDOWS: usize = 65;
+const WINDOW_SIZE: usize = 8;
+
+fn format_limbs(limbs: [u64; 5]) -> String {
+    let limbs: Vec<String> = limbs.iter().map(|l| format!("{:#X}", l)).collect();
+
+    format!("[{}]", limbs.join(", "))
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=src/field.rs");
+    println!("cargo:rerun-if-changed=src/field_inversion.rs");
+    println!("cargo:rerun-if-changed=src/field_multiplication.rs");
+    println!("cargo:rerun-if-changed=src/group.rs");
+
+    let mut table = String::new();
+
+    writeln!(
+        table,
+        "pub static GENERATOR_TABLE: [[([u64; 5], [u64; 5]); {}]; {}] = [",
+        WINDOW_SIZE, WINDOWS
+    )
+    .unwrap();
+
+    let mut base = group::AffinePoint::GENERATOR.to_point();
+    let mut points = Vec::with_capacity(WINDOWS * WINDOW_SIZE);
+
+    for _ in 0..WINDOWS {
+        let mut point = base;
+
+        for _ in 0..WINDOW_SIZE {
+            points.push(point);
+            point = point.add(base);
+        }
+
+        for _ in 0..4 {
+            base = base.double();
+        }
+    }
+
+    for window in group::batch_to_affine(&points).chunks(WINDOW_SIZE) {
+        writeln!(table, "    [").unwrap();
+
+        for point in window {
+            let x = point.x.normalize().limbs;
+            let y = point.y.normalize().limbs;
+
+            writeln!(table, "        ({}, {}),", format_limbs(x), format_limbs(y)).unwrap();
+        }
+
+        writeln!(table, "    ],").unwrap();
+    }
+
+    writeln!(table, "];").unwrap();
+
+    let path = Path::new(&env::var("OUT_DIR").unwrap()).join("generator_table.rs");
+    fs::write(path, table).unwrap();
+}
@@ -0,0 +1,313 @@
+use super::field;
+use super::group;
+use super::group_multiplication;
+use super::hash;
+use super::hmac;
+use super::scalar;
+use super::schnorr::GROUP_ORDER;
+
+// p - n, the x coordinate of R can only exceed n if x mod n is less than this
+const PRIME_MINUS_ORDER: [u8; 32] = hex_literal::hex!(
+    "00000000000000000000000000000001"
+    "4551231950B75FC4402DA1722FC9BAEE"
+);
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum EcdsaError {
+    SignatureOutOfBounds,
+    FailedToSolve,
+    IsNeutral,
+    IsHigh,
+    NotEqual,
+}
+
+fn hmac(key: &[u8; 32], data: &[&[u8]]) -> [u8; 32] {
+    let mut hmac = hmac::Hmac::<hash::Sha256>::new(key);
+
+    for d in data {
+        hmac.write(d);
+    }
+
+    hmac.finish()
+}
+
+// Derives the nonce from the secret key and the message hash with HMAC-SHA256
+// as specified in section 3.2 of RFC6979, where the message hash is reduced
+// modulo n before it is used.
+fn nonce(secret_key: [u8; 32], message_hash: [u8; 32]) -> scalar::Scalar {
+    let h = scalar::Scalar::decode(message_hash).encode();
+
+    let mut v = [0x01; 32];
+    let mut k = [0x00; 32];
+
+    k = hmac(&k, &[&v, &[0x00], &secret_key, &h]);
+    v = hmac(&k, &[&v]);
+    k = hmac(&k, &[&v, &[0x01], &secret_key, &h]);
+    v = hmac(&k, &[&v]);
+
+    loop {
+        v = hmac(&k, &[&v]);
+
+        if v != [0; 32] && v < GROUP_ORDER {
+            return scalar::Scalar::decode(v);
+        }
+
+        k = hmac(&k, &[&v, &[0x00]]);
+        v = hmac(&k, &[&v]);
+    }
+}
+
+// Signs the hash of a message with a deterministic nonce. The returned s is
+// always normalized to the lower half of the range, which is the only form
+// accepted by verify_signature. The recovery id encodes the parity of the y
+// coordinate of R in bit 0 and whether its x coordinate exceeds n in bit 1.
+pub fn sign(secret_key: [u8; 32], message_hash: [u8; 32]) -> (([u8; 32], [u8; 32]), u8) {
+    assert!(secret_key != [0; 32]);
+    assert!(secret_key < GROUP_ORDER);
+
+    let d = scalar::Scalar::decode(secret_key);
+    let z = scalar::Scalar::decode(message_hash);
+    let k = nonce(secret_key, message_hash);
+
+    let (x, y) = group_multiplication::multiply_generator(k.encode()).to_affine();
+    let r = scalar::Scalar::decode(x.encode());
+
+    let s = k.inverse().unwrap() * (z + r * d);
+
+    // RFC6979 would continue with the next nonce, but finding a nonce for
+    // which this fails is as hard as computing discrete logarithms.
+    assert!(!r.is_zero());
+    assert!(!s.is_zero());
+
+    let mut recovery_id = (!y.is_even() as u8) | ((x.encode() >= GROUP_ORDER) as u8) << 1;
+
+    // negating s corresponds to negating the nonce and thereby R
+    let is_high = s.is_high();
+    recovery_id ^= is_high as u8;
+    let s = s.cond_negate(is_high);
+
+    ((r.encode(), s.encode()), recovery_id)
+}
+
+pub fn verify_signature(
+    public_key: group::AffinePoint,
+    message_hash: [u8; 32],
+    (r, s): ([u8; 32], [u8; 32]),
+) -> Result<(), EcdsaError> {
+    if r == [0; 32] || r >= GROUP_ORDER || s == [0; 32] || s >= GROUP_ORDER {
+        return Err(EcdsaError::SignatureOutOfBounds);
+    }
+
+    let s = scalar::Scalar::decode(s);
+
+    if s.is_high() {
+        return Err(EcdsaError::IsHigh);
+    }
+
+    let z = scalar::Scalar::decode(message_hash);
+    let w = s.inverse().unwrap();
+
+    let u1 = z * w;
+    let u2 = scalar::Scalar::decode(r) * w;
+
+    let h = group_multiplication::multiply_multi_endomorphism(
+        &[
+            (group::AffinePoint::GENERATOR, u1.encode()),
+            (public_key, u2.encode()),
+        ],
+        group_multiplication::WNAF_WIDTH,
+    );
+
+    let group::Point::P(x, _, z) = h else {
+        return Err(EcdsaError::IsNeutral);
+    };
+
+    // The affine x coordinate is less than p, so it is congruent to r modulo n
+    // if it equals r or r + n. As in BIP340 both candidates are compared with
+    // x / z^2 without an inversion.
+    let z2 = z.square();
+    let r_element = field::Element::decode(r);
+
+    if x == r_element * z2 {
+        return Ok(());
+    }
+
+    if r < PRIME_MINUS_ORDER && x == (r_element + field::Element::decode(GROUP_ORDER)) * z2 {
+        return Ok(());
+    }
+
+    Err(EcdsaError::NotEqual)
+}
+
+// Recovers the public key for which (r, s) is a valid signature of the message
+// hash, where R = (x, y) is the point with x = r + n * (recovery_id >> 1) and
+// the parity of y given by the lowest bit of the recovery id. Since
+// s * R = z * G + r * Q the public key is Q = r^-1 * (s * R - z * G).
+pub fn recover(
+    message_hash: [u8; 32],
+    (r, s): ([u8; 32], [u8; 32]),
+    recovery_id: u8,
+) -> Result<group::Point, EcdsaError> {
+    assert!(recovery_id < 4);
+
+    if r == [0; 32] || r >= GROUP_ORDER || s == [0; 32] || s >= GROUP_ORDER {
+        return Err(EcdsaError::SignatureOutOfBounds);
+    }
+
+    let mut x = field::Element::decode(r);
+
+    if recovery_id & 2 != 0 {
+        if r >= PRIME_MINUS_ORDER {
+            return Err(EcdsaError::SignatureOutOfBounds);
+        }
+
+        x = x + field::Element::decode(GROUP_ORDER);
+    }
+
+    let nonce_point = group::AffinePoint::lift_x(x.normalize(), recovery_id & 1 != 0)
+        .ok_or(EcdsaError::FailedToSolve)?;
+
+    let r_inverse = scalar::Scalar::decode(r).inverse().unwrap();
+    let u1 = -(scalar::Scalar::decode(message_hash) * r_inverse);
+    let u2 = scalar::Scalar::decode(s) * r_inverse;
+
+    let q = group_multiplication::multiply_multi_endomorphism(
+        &[
+            (group::AffinePoint::GENERATOR, u1.encode()),
+            (nonce_point, u2.encode()),
+        ],
+        group_multiplication::WNAF_WIDTH,
+    );
+
+    if q.is_neutral() {
+        return Err(EcdsaError::IsNeutral);
+    }
+
+    Ok(q)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ecdsa::EcdsaError;
+    use crate::field;
+    use crate::group;
+    use crate::hash;
+
+    fn decode_hex<const N: usize>(hex: &str) -> [u8; N] {
+        (0..N)
+            .map(|i| u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap())
+            .collect::<Vec<u8>>()
+            .try_into()
+            .unwrap()
+    }
+
+    fn sha256(message: &[u8]) -> [u8; 32] {
+        let mut hasher = hash::Sha256::empty();
+        hasher.write(message);
+        hasher.finish()
+    }
+
+    fn public_point(uncompressed: &str) -> group::AffinePoint {
+        group::AffinePoint {
+            x: field::Element::decode(decode_hex(&uncompressed[2..66])),
+            y: field::Element::decode(decode_hex(&uncompressed[66..])),
+        }
+    }
+
+    // (secret key, message, signature, uncompressed public key, recovery id)
+    const TEST_VECTORS: [(&str, &[u8], &str, &str, u8); 3] = [
+        (
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            b"Satoshi Nakamoto",
+            "934B1EA10A4B3C1757E2B0C017D0B6143CE3C9A7E6A4A49860D7A6AB210EE3D8\
+             2442CE9D2B916064108014783E923EC36B49743E2FFA1C4496F01A512AAFD9E5",
+            "0479BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F81798\
+             483ADA7726A3C4655DA4FBFC0E1108A8FD17B448A68554199C47D08FFB10D4B8",
+            1,
+        ),
+        (
+            "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364140",
+            b"Satoshi Nakamoto",
+            "FD567D121DB66E382991534ADA77A6BD3106F0A1098C231E47993447CD6AF2D0\
+             6B39CD0EB1BC8603E159EF5C20A5C8AD685A45B06CE9BEBED3F153D10D93BED5",
+            "0479BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F81798\
+             B7C52588D95C3B9AA25B0403F1EEF75702E84BB7597AABE663B82F6F04EF2777",
+            0,
+        ),
+        (
+            "B7E151628AED2A6ABF7158809CF4F3C762E7160F38B4DA56A784D9045190CFEF",
+            b"message",
+            "B49007FD60B8AB5599398A0873CEBF27678EE5547EB4567102BA086299206B9F\
+             06E877EF67BFF7DDFD5610DD24194B63DEA1D23E15D7A926AF92B1E14B5E4C2E",
+            "04DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659\
+             2CE19B946C4EE58546F5251D441A065EA50735606985E5B228788BEC4E582898",
+            0,
+        ),
+    ];
+
+    #[test]
+    fn sign() {
+        for (secret_key, message, signature, _, recovery_id) in TEST_VECTORS {
+            let signature: [u8; 64] = decode_hex(signature);
+            let ((r, s), id) = super::sign(decode_hex(secret_key), sha256(message));
+
+            assert!(r[..] == signature[..32]);
+            assert!(s[..] == signature[32..]);
+            assert!(id == recovery_id);
+        }
+    }
+
+    #[test]
+    fn recover() {
+        for (_, message, signature, public_key, recovery_id) in TEST_VECTORS {
+            let signature: [u8; 64] = decode_hex(signature);
+            let r: [u8; 32] = signature[..32].try_into().unwrap();
+            let s: [u8; 32] = signature[32..].try_into().unwrap();
+
+            let public_key = public_point(public_key);
+            let recovered = super::recover(sha256(message), (r, s), recovery_id)
+                .unwrap()
+                .to_affine();
+
+            assert!(recovered.x == public_key.x);
+            assert!(recovered.y == public_key.y);
+
+            // the other parity of R yields a different key
+            let other = super::recover(sha256(message), (r, s), recovery_id ^ 1)
+                .unwrap()
+                .to_affine();
+
+            assert!(other.x != public_key.x);
+        }
+    }
+
+    #[test]
+    fn verify_signature() {
+        for (_, message, signature, public_key, _) in TEST_VECTORS {
+            let signature: [u8; 64] = decode_hex(signature);
+            let r: [u8; 32] = signature[..32].try_into().unwrap();
+            let s: [u8; 32] = signature[32..].try_into().unwrap();
+
+            let public_key = public_point(public_key);
+
+            assert!(super::verify_signature(public_key, sha256(message), (r, s)).is_ok());
+
+            assert!(
+                super::verify_signature(public_key, sha256(b"massage"), (r, s))
+                    == Err(EcdsaError::NotEqual)
+            );
+
+            let high_s = (-crate::scalar::Scalar::decode(s)).encode();
+
+            assert!(
+                super::verify_signature(public_key, sha256(message), (r, high_s))
+                    == Err(EcdsaError::IsHigh)
+            );
+
+            assert!(
+                super::verify_signature(public_key, sha256(message), ([0; 32], s))
+                    == Err(EcdsaError::SignatureOutOfBounds)
+            );
+        }
+    }
+}
@@ -15,6 +15,37 @@ pub const ONE: Element = Element {
     magnitude: 0,
 };
 
+// Replaces every element with its inverse using Montgomery's trick, which
+// costs a single inversion and three multiplications per element. Zero has no
+// inverse and is mapped to zero without affecting the other elements.
+pub fn batch_invert(elements: &mut [Element]) {
+    // products[i] is the product of all nonzero elements before index i
+    let mut products = Vec::with_capacity(elements.len());
+    let mut product = ONE;
+
+    for x in elements.iter() {
+        products.push(product);
+
+        if !x.is_zero() {
+            product = product * *x;
+        }
+    }
+
+    let mut inverse = product.inverse();
+
+    for (x, p) in elements.iter_mut().zip(products).rev() {
+        if x.is_zero() {
+            *x = ZERO;
+            continue;
+        }
+
+        // inverse is the inverse of the product of all nonzero elements up to x
+        let x_inverse = inverse * p;
+        inverse = inverse * *x;
+        *x = x_inverse;
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct Element {
     pub limbs: [u64; 5],
@@ -63,49 +94,37 @@ impl Element {
         const MASK_52: u64 = 0xFFFFFFFFFFFFF;
         const MASK_48: u64 = 0xFFFFFFFFFFFF;
         const R: u64 = 0x1000003D1;
-        const P: [u64; 5] = [
-            0xFFFFEFFFFFC2F,
-            0xFFFFFFFFFFFFF,
-            0xFFFFFFFFFFFFF,
-            0xFFFFFFFFFFFFF,
-            0xFFFFFFFFFFFF,
-        ];
 
-        // after reduction the value of of a representation is less than 2p
+        // after reduction the value of a representation is less than 2p
         // since l_4 <= MASK_48 + 2^{12} < 2 * MASK_48 and the bits of l_3
         // that overlap with l_4 are zero
         let mut limbs = self.reduce().limbs;
 
-        // Since there is no overlap in the limbs we can compare the arrays in
-        // colexicographical order to determine if the representation is normalized
-        let normalized = limbs.iter().rev().lt(P.iter().rev());
-
-        if !normalized {
-            // Since a reduced representations value is less then 2p we
-            // have to subtract P only once to achieve a normalized representation.
-            limbs[0] += R;
-            limbs[1] += limbs[0] >> 52;
-            limbs[2] += limbs[1] >> 52;
-            limbs[3] += limbs[2] >> 52;
-            limbs[4] += limbs[3] >> 52;
-
-            // Since the value of a representaion is larger or equal to P
-            // and P + R = 2^{256} we carry a 1 to bit 49 of limb 4
-            // if it was not set to 1 already. Furthermore the representations
-            // value is smaller then 2p + R = 2^{256} + P <= 2^{257}
-            assert!(limbs[4] >> 48 == 1);
-
-            limbs[0] &= MASK_52;
-            limbs[1] &= MASK_52;
-            limbs[2] &= MASK_52;
-            limbs[3] &= MASK_52;
-
-            // by zeroing bit 49 we subtract 2^{256} and maintain congruecy modulo P
-            limbs[4] &= MASK_48;
-        };
+        // The value of the representation is larger or equal to P if bit 49 of
+        // limb 4 is set or if all limbs except limb 0 are equal to those of P
+        // and limb 0 is larger or equal. The comparisons are evaluated without
+        // branches, such that the running time does not depend on the value.
+        let m = limbs[1] & limbs[2] & limbs[3];
+        let overflow = (limbs[4] >> 48)
+            | ((limbs[4] == MASK_48) & (m == MASK_52) & (limbs[0] >= 0xFFFFEFFFFFC2F)) as u64;
+
+        // Since a reduced representations value is less than 2p we have to
+        // subtract P at most once. We always add R = 2^{256} - P times the
+        // overflow bit, which carries a 1 to bit 49 of limb 4 if the value is
+        // larger or equal to P and it was not set to 1 already.
+        limbs[0] += overflow * R;
+        limbs[1] += limbs[0] >> 52;
+        limbs[2] += limbs[1] >> 52;
+        limbs[3] += limbs[2] >> 52;
+        limbs[4] += limbs[3] >> 52;
 
-        // verify the representation is normalized
-        assert!(limbs.iter().rev().lt(P.iter().rev()));
+        limbs[0] &= MASK_52;
+        limbs[1] &= MASK_52;
+        limbs[2] &= MASK_52;
+        limbs[3] &= MASK_52;
+
+        // by zeroing bit 49 we subtract 2^{256} and maintain congruency modulo P
+        limbs[4] &= MASK_48;
 
         Self {
             limbs,
@@ -149,7 +168,23 @@ impl Element {
     }
 
     pub fn is_zero(&self) -> bool {
-        self.normalize().limbs == [0, 0, 0, 0, 0]
+        // accumulate all limbs instead of comparing them one by one
+        let limbs = self.normalize().limbs;
+        (limbs[0] | limbs[1] | limbs[2] | limbs[3] | limbs[4]) == 0
+    }
+
+    pub fn ct_eq(self, rhs: Self) -> bool {
+        (self - rhs).is_zero()
+    }
+
+    // Returns true if the element is a square, including zero. The variable time
+    // Jacobi symbol is used unless it fails to converge, in which case we fall
+    // back to the exponentiation of sqrt.
+    pub fn is_square(self) -> bool {
+        match field_inversion::jacobi(self) {
+            Some(jacobi) => jacobi >= 0,
+            None => self.sqrt().is_some(),
+        }
     }
 
     pub fn is_even(self) -> bool {
@@ -184,6 +219,16 @@ impl Element {
         }
     }
 
+    // Returns rhs if flag is set and self otherwise, without branching on flag.
+    pub fn cmov(self, rhs: Self, flag: bool) -> Self {
+        let mask = (flag as u64).wrapping_neg();
+
+        Self {
+            limbs: [0, 1, 2, 3, 4].map(|i| self.limbs[i] ^ (mask & (self.limbs[i] ^ rhs.limbs[i]))),
+            magnitude: cmp::max(self.magnitude, rhs.magnitude),
+        }
+    }
+
     pub fn square(self) -> Self {
         field_multiplication::square(self)
     }
@@ -191,11 +236,59 @@ impl Element {
     pub fn inverse(self) -> Self {
         field_inversion::invert(self)
     }
+
+    pub fn inverse_constant_time(self) -> Self {
+        field_inversion::invert_constant_time(self)
+    }
+
+    pub fn square_n(self, n: usize) -> Self {
+        (0..n).fold(self, |x, _| x.square())
+    }
+
+    // Computes x^(2^2 - 1), x^(2^22 - 1) and x^(2^223 - 1), which are shared by
+    // the addition chains for the exponents p - 2 and (p + 1) / 4 whose binary
+    // representations start with 223 ones.
+    pub fn power_chain(self) -> (Self, Self, Self) {
+        // x_n denotes x^(2^n - 1)
+        let x1 = self.reduce();
+        let x2 = x1.square() * x1;
+        let x3 = x2.square() * x1;
+        let x6 = x3.square_n(3) * x3;
+        let x9 = x6.square_n(3) * x3;
+        let x11 = x9.square_n(2) * x2;
+        let x22 = x11.square_n(11) * x11;
+        let x44 = x22.square_n(22) * x22;
+        let x88 = x44.square_n(44) * x44;
+        let x176 = x88.square_n(88) * x88;
+        let x220 = x176.square_n(44) * x44;
+        let x223 = x220.square_n(3) * x3;
+
+        (x2, x22, x223)
+    }
+
+    // Since p = 3 mod 4 a square root of x is x^((p + 1) / 4) if it exists,
+    // which is computed with the addition chain of libsecp256k1 consisting of
+    // 253 squarings and 13 multiplications.
+    pub fn sqrt(self) -> Option<Self> {
+        let (x2, x22, x223) = self.power_chain();
+
+        // The exponent (p + 1) / 4 consists of 223 ones followed by the bits
+        // 0 1111111111111111111111 000000 11 00
+        let t = x223.square_n(23) * x22;
+        let t = t.square_n(6) * x2;
+        let root = t.square_n(2);
+
+        if root.square() == self {
+            Some(root)
+        } else {
+            None
+        }
+    }
 }
 
 impl PartialEq for Element {
     fn eq(&self, rhs: &Self) -> bool {
-        (*self - *rhs).is_zero()
+        self.ct_eq(*rhs)
     }
 }
 
@@ -220,6 +313,7 @@ impl Add for Element {
 impl Sub for Element {
     type Output = Self;
 
+    #[allow(clippy::suspicious_arithmetic_impl)]
     fn sub(self, rhs: Self) -> Self {
         self + rhs.negative()
     }
@@ -236,6 +330,7 @@ impl Mul for Element {
 impl Div for Element {
     type Output = Self;
 
+    #[allow(clippy::suspicious_arithmetic_impl)]
     fn div(self, rhs: Self) -> Self {
         self * rhs.inverse()
     }
@@ -276,4 +371,107 @@ mod tests {
         assert!(super::ONE / super::ONE == super::ONE);
         assert!(GENERATOR_X / GENERATOR_X == super::ONE);
     }
+
+    #[test]
+    fn normalize() {
+        const P: super::Element = super::Element {
+            limbs: [
+                0xFFFFEFFFFFC2F,
+                0xFFFFFFFFFFFFF,
+                0xFFFFFFFFFFFFF,
+                0xFFFFFFFFFFFFF,
+                0xFFFFFFFFFFFF,
+            ],
+            magnitude: 0,
+        };
+
+        assert!(P.normalize().limbs == super::ZERO.limbs);
+        assert!((P + super::ONE).normalize().limbs == super::ONE.limbs);
+        assert!(P.is_zero());
+
+        let p_minus_one = super::Element {
+            limbs: [
+                0xFFFFEFFFFFC2E,
+                P.limbs[1],
+                P.limbs[2],
+                P.limbs[3],
+                P.limbs[4],
+            ],
+            magnitude: 0,
+        };
+
+        assert!(p_minus_one.normalize().limbs == p_minus_one.limbs);
+        assert!(
+            (p_minus_one + p_minus_one).normalize().limbs
+                == super::ONE.negative().double().normalize().limbs
+        );
+        assert!(!p_minus_one.is_zero());
+    }
+
+    #[test]
+    fn cmov() {
+        let two = super::ONE.double();
+
+        assert!(super::ONE.cmov(two, false).ct_eq(super::ONE));
+        assert!(super::ONE.cmov(two, true).ct_eq(two));
+    }
+
+    #[test]
+    fn batch_invert() {
+        let mut elements = vec![super::ONE.double()];
+
+        for i in 0..16 {
+            let x = if i % 5 == 2 {
+                super::ZERO
+            } else {
+                elements[i].square() + super::ONE
+            };
+
+            elements.push(x);
+        }
+
+        let mut inverses = elements.clone();
+        super::batch_invert(&mut inverses);
+
+        for (x, x_inverse) in elements.into_iter().zip(inverses) {
+            if x.is_zero() {
+                assert!(x_inverse.is_zero());
+            } else {
+                assert!(x_inverse == x.inverse());
+            }
+        }
+
+        let mut empty = [];
+        super::batch_invert(&mut empty);
+    }
+
+    #[test]
+    fn sqrt() {
+        let mut x = super::ONE.double();
+
+        for _ in 0..32 {
+            let root = x.square().sqrt().unwrap();
+
+            assert!(root == x || root == x.negative());
+
+            // -1 is not a square modulo p since p = 3 mod 4
+            assert!((x.square().negative()).sqrt().is_none());
+
+            x = x.square() + super::ONE;
+        }
+
+        assert!(super::ZERO.sqrt().unwrap().is_zero());
+    }
+
+    #[test]
+    fn inverse_constant_time() {
+        let mut x = super::ONE.double();
+
+        for _ in 0..32 {
+            assert!(x.inverse_constant_time() == x.inverse());
+            x = x.square() + super::ONE;
+        }
+
+        assert!(super::ZERO.inverse_constant_time().is_zero());
+    }
 }
@@ -48,17 +48,6 @@ pub fn invert(x: field::Element) -> field::Element {
         0xFFFFFFFFFFFF,
     ];
 
-    const INVERSE_2_POW_728: field::Element = field::Element {
-        limbs: [
-            0xA9357C4C3E74,
-            0x359FCD6E223C,
-            0x88010D51154F6,
-            0x8F7C917CA2A4C,
-            0x45F105208471,
-        ],
-        magnitude: 0,
-    };
-
     const INVERSE_2_POW_744: field::Element = field::Element {
     limbs: [
         0x223BFB1017899,
@@ -105,6 +94,97 @@ pub fn invert(x: field::Element) -> field::Element {
     f * d * INVERSE_2_POW_744
 }
 
+// Computes x^(p - 2) with a fixed addition chain of 255 squarings and 15
+// multiplications taken from libsecp256k1, such that unlike invert the running
+// time does not depend on x. The inverse of zero is zero.
+pub fn invert_constant_time(x: field::Element) -> field::Element {
+    let (x2, x22, x223) = x.power_chain();
+    let x1 = x.reduce();
+
+    // The exponent p - 2 consists of 223 ones followed by the bits
+    // 0 1111111111111111111111 0000 1 011 0 1
+    let t = x223.square_n(23) * x22;
+    let t = t.square_n(5) * x1;
+    let t = t.square_n(3) * x2;
+    t.square_n(2) * x1
+}
+
+// Computes the Jacobi symbol of x modulo p, which is 1 for nonzero squares and
+// -1 for non-squares. The positive divsteps used below are not known to
+// terminate within a fixed number of iterations, so None is returned if they
+// have not converged after 25 * 62 steps.
+pub fn jacobi(x: field::Element) -> Option<i64> {
+    const PRIME: [u64; 5] = [
+        0xFFFFEFFFFFC2F,
+        0xFFFFFFFFFFFFF,
+        0xFFFFFFFFFFFFF,
+        0xFFFFFFFFFFFFF,
+        0xFFFFFFFFFFFF,
+    ];
+
+    if x.is_zero() {
+        return Some(0);
+    }
+
+    let mut eta = -1;
+    let mut jacobi = 0;
+    let mut f = to_i128_offset_62(PRIME);
+    let mut g = to_i128_offset_62(x.normalize().limbs);
+
+    let mut transition_matrix;
+
+    for _ in 0..25 {
+        // f and g are non-negative, so the bottom 64 bits are given by the first two limbs
+        let f_low = (f[0] | (f[1] << 62)) as u64;
+        let g_low = (g[0] | (g[1] << 62)) as u64;
+
+        (eta, transition_matrix, jacobi) = update_eta_jacobi(eta, f_low, g_low, jacobi);
+
+        (f, g) = update_fg(f, g, transition_matrix);
+
+        // f converges to gcd(p, x) = 1, where the Jacobi symbol (g / 1) is 1
+        if f == [1, 0, 0, 0, 0] {
+            return Some(1 - 2 * jacobi as i64);
+        }
+    }
+
+    None
+}
+
+fn update_eta_jacobi(
+    mut eta: i64,
+    mut f: u64,
+    mut g: u64,
+    mut jacobi: u64,
+) -> (i64, (i64, i64, i64, i64), u64) {
+    //Compute eta = -delta and transition matrix t after N positive divsteps (multiplied by 2^N), which add f to g
+    //instead of subtracting it such that both remain non-negative. Bit 0 of jacobi tracks the sign of the symbol.
+
+    let (mut u, mut v, mut q, mut r): (u64, u64, u64, u64) = (1, 0, 0, 1); // start with identity matrix
+
+    for _ in 0..62 {
+        assert!(f & 1 == 1);
+        if g & 1 == 1 {
+            if eta < 0 {
+                eta = -eta;
+                (f, g) = (g, f);
+                (u, v, q, r) = (q, r, u, v);
+                // quadratic reciprocity, the sign changes if f and g are both 3 mod 4
+                jacobi ^= (f & g) >> 1;
+            }
+            g = g.wrapping_add(f);
+            (q, r) = (q + u, r + v);
+        }
+        // halving g changes the sign if f is 3 or 5 mod 8
+        eta -= 1;
+        g >>= 1;
+        (u, v) = (u << 1, v << 1);
+        jacobi ^= (f >> 1) ^ (f >> 2);
+    }
+
+    (eta, (u as i64, v as i64, q as i64, r as i64), jacobi & 1)
+}
+
 fn update_delta(mut delta: i64, mut f: i64, mut g: i64) -> (i64, (i64, i64, i64, i64)) {
     //Compute delta and transition matrix t after N divsteps (multiplied by 2^N).
 
@@ -255,7 +335,7 @@ fn update_de(
 
 #[cfg(test)]
 mod tests {
-    use crate::field::{self, Element};
+    use crate::field;
 
 
     #[test]
@@ -280,11 +360,28 @@ mod tests {
        
     }
 
+    #[test]
+    fn jacobi() {
+        let mut x = field::ONE.double();
+
+        for _ in 0..32 {
+            assert!(super::jacobi(x.square()) == Some(1));
+            assert!(super::jacobi(x.square().negative()) == Some(-1));
+
+            let expected = if x.sqrt().is_some() { 1 } else { -1 };
+            assert!(super::jacobi(x) == Some(expected));
+
+            x = x.square() + field::ONE;
+        }
+
+        assert!(super::jacobi(field::ZERO) == Some(0));
+    }
+
     #[test]
     fn inverse_744() {
 
         let mut x = field::ONE;
-        for i in 0..744{
+        for _ in 0..744{
             x = x.double();
             x = x.reduce();
         }
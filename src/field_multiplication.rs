@@ -1,5 +1,4 @@
 use crate::field;
-use std::u128;
 
 pub fn multiply(a: field::Element, b: field::Element) -> field::Element {
     const MASK_48: u128 = 0xFFFFFFFFFFFF;
@@ -33,8 +33,65 @@ const GENERATOR_Y: field::Element = field::Element {
     magnitude: 0,
 };
 
+#[cfg(test)]
 pub const GENERATOR: Point = Point::P(GENERATOR_X, GENERATOR_Y, field::ONE);
 
+// A point given by its affine coordinates (x, y), which can therefore not be
+// the neutral element.
+#[derive(Debug, Clone, Copy)]
+pub struct AffinePoint {
+    pub x: field::Element,
+    pub y: field::Element,
+}
+
+impl AffinePoint {
+    pub const GENERATOR: Self = Self {
+        x: GENERATOR_X,
+        y: GENERATOR_Y,
+    };
+
+    // Returns the point with the given x coordinate and a y coordinate of the
+    // given parity, if x^3 + 7 is a square.
+    pub fn lift_x(x: field::Element, is_odd: bool) -> Option<Self> {
+        let y = (x.square() * x + 7.into()).sqrt()?;
+
+        if y.is_even() == is_odd {
+            Some(Self { x, y: y.negative() })
+        } else {
+            Some(Self { x, y })
+        }
+    }
+
+    pub fn is_on_curve(self) -> bool {
+        self.y.square() == self.x.square() * self.x + 7.into()
+    }
+
+    pub fn to_point(self) -> Point {
+        Point::P(self.x, self.y, field::ONE)
+    }
+
+    pub fn negative(self) -> Self {
+        Self {
+            y: self.y.negative(),
+            ..self
+        }
+    }
+
+    pub fn cmov(self, rhs: Self, flag: bool) -> Self {
+        Self {
+            x: self.x.cmov(rhs.x, flag),
+            y: self.y.cmov(rhs.y, flag),
+        }
+    }
+
+    pub fn negate_if(self, flag: bool) -> Self {
+        Self {
+            y: self.y.cmov(self.y.negative(), flag).reduce(),
+            ..self
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum Point {
     E,
@@ -42,6 +99,7 @@ pub enum Point {
 }
 
 impl Point {
+    #[cfg(test)]
     fn verify(self) {
         if let Point::P(x, y, z) = self {
             let z2 = z.square();
@@ -51,31 +109,42 @@ impl Point {
         }
     }
 
+    #[cfg(test)]
     pub fn affine_x(self) -> field::Element {
         match self {
             Self::E => panic!("affine_x() has been called on the neutral element"),
-            Self::P(x, y, z) => x / z.square(),
+            Self::P(x, _, z) => x / z.square(),
         }
     }
 
+    #[cfg(test)]
     pub fn affine_y(self) -> field::Element {
         match self {
             Self::E => panic!("affine_x() has been called on the neutral element"),
-            Self::P(x, y, z) => y / (z.square() * z),
+            Self::P(_, y, z) => y / (z.square() * z),
         }
     }
 
-    pub fn is_neutral(self) -> bool {
+    // Computes both affine coordinates with a single inversion.
+    pub fn to_affine(self) -> AffinePoint {
         match self {
-            Point::E => true,
-            Point::P(..) => false,
+            Self::E => panic!("to_affine() has been called on the neutral element"),
+            Self::P(x, y, z) => {
+                let z_inverse = z.inverse();
+                let z_inverse2 = z_inverse.square();
+
+                AffinePoint {
+                    x: x * z_inverse2,
+                    y: y * z_inverse2 * z_inverse,
+                }
+            }
         }
     }
 
-    pub fn negative(self) -> Self {
+    pub fn is_neutral(self) -> bool {
         match self {
-            Self::E => Self::E,
-            Self::P(x, y, z) => Self::P(x, y.negative(), z),
+            Point::E => true,
+            Point::P(..) => false,
         }
     }
 
@@ -146,6 +215,179 @@ impl Point {
             }
         }
     }
+
+    // Adds an affine point, which saves four multiplications and a squaring
+    // compared to add since the z coordinate of rhs is one.
+    pub fn add_affine(self, rhs: AffinePoint) -> Self {
+        match self {
+            Point::E => rhs.to_point(),
+            Point::P(ax, ay, az) => {
+                let az2 = az.square();
+
+                let bx = rhs.x * az2;
+                let by = rhs.y * az2 * az;
+
+                // ax can have a magnitude of three after a doubling
+                let h = (bx - ax).reduce();
+                let i = ay - by;
+
+                if h.is_zero() {
+                    if i.is_zero() {
+                        return self.double();
+                    } else {
+                        assert!(ay == by.negative());
+                        return Self::E;
+                    }
+                }
+
+                let h2 = h.square().negative();
+                let h3 = h2 * h;
+                let t = ax * h2;
+
+                let rx = i.square() + h3 + t.double();
+                let ry = i * (t + rx) + h3 * ay;
+                let rz = az * h;
+
+                Self::P(rx, ry, rz)
+            }
+        }
+    }
+}
+
+// Converts all points to affine coordinates with a single inversion by
+// inverting the z coordinates in a batch.
+pub fn batch_to_affine(points: &[Point]) -> Vec<AffinePoint> {
+    let mut z: Vec<field::Element> = points
+        .iter()
+        .map(|point| match point {
+            Point::E => panic!("batch_to_affine() has been called on the neutral element"),
+            Point::P(_, _, z) => *z,
+        })
+        .collect();
+
+    field::batch_invert(&mut z);
+
+    points
+        .iter()
+        .zip(z)
+        .map(|(point, z_inverse)| {
+            let Point::P(x, y, _) = *point else {
+                unreachable!("neutral elements have been rejected above");
+            };
+
+            let z_inverse2 = z_inverse.square();
+
+            AffinePoint {
+                x: x * z_inverse2,
+                y: y * z_inverse2 * z_inverse,
+            }
+        })
+        .collect()
+}
+
+// A point in homogeneous projective coordinates (X : Y : Z) with x = X / Z and
+// y = Y / Z, where the neutral element is (0 : 1 : 0). Unlike Point every
+// operation is free of branches on the coordinates, which makes it suitable
+// for computations on secret data.
+#[derive(Debug, Clone, Copy)]
+pub struct ProjectivePoint {
+    pub x: field::Element,
+    pub y: field::Element,
+    pub z: field::Element,
+}
+
+impl ProjectivePoint {
+    pub const NEUTRAL: Self = Self {
+        x: field::ZERO,
+        y: field::ONE,
+        z: field::ZERO,
+    };
+
+    // Computes the affine coordinates in constant time, the neutral element is
+    // mapped to (0, 0).
+    pub fn to_affine(self) -> (field::Element, field::Element) {
+        let z = self.z.inverse_constant_time();
+
+        (self.x * z, self.y * z)
+    }
+
+    #[cfg(test)]
+    pub fn to_point(self) -> Point {
+        if self.z.is_zero() {
+            return Point::E;
+        }
+
+        // (X : Y : Z) corresponds to the jacobian coordinates (XZ, YZ^2, Z)
+        Point::P(self.x * self.z, self.y * self.z.square(), self.z)
+    }
+
+    pub fn cmov(self, rhs: Self, flag: bool) -> Self {
+        Self {
+            x: self.x.cmov(rhs.x, flag),
+            y: self.y.cmov(rhs.y, flag),
+            z: self.z.cmov(rhs.z, flag),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn add(self, rhs: Self) -> Self {
+        /* Complete addition formula for a = 0 from Renes, Costello and Batina,
+         * "Complete addition formulas for prime order elliptic curves",
+         * Algorithm 7, rearranged as in the k256 crate with b3 = 3 * b = 21:
+         * RX = (X1Y2 + X2Y1) * (Y1Y2 - b3 * Z1Z2) - b3 * (Y1Z2 + Y2Z1) * (X1Z2 + X2Z1)
+         * RY = (Y1Y2 + b3 * Z1Z2) * (Y1Y2 - b3 * Z1Z2) + 3 * b3 * X1X2 * (X1Z2 + X2Z1)
+         * RZ = (Y1Z2 + Y2Z1) * (Y1Y2 + b3 * Z1Z2) + 3 * X1X2 * (X1Y2 + X2Y1)
+         */
+
+        let xx = self.x * rhs.x;
+        let yy = self.y * rhs.y;
+        let zz = self.z * rhs.z;
+
+        let xy = (self.x + self.y) * (rhs.x + rhs.y) - (xx + yy);
+        let yz = (self.y + self.z) * (rhs.y + rhs.z) - (yy + zz);
+        let xz = (self.x + self.z) * (rhs.x + rhs.z) - (xx + zz);
+
+        Self::combine(xx, yy, zz, xy, yz, xz)
+    }
+
+    // Mixed addition with an affine point following Algorithm 8 of the same
+    // paper, which substitutes Z2 = 1 into the products above and remains
+    // complete since rhs can not be the neutral element.
+    pub fn add_affine(self, rhs: AffinePoint) -> Self {
+        let xx = self.x * rhs.x;
+        let yy = self.y * rhs.y;
+
+        let xy = (self.x + self.y) * (rhs.x + rhs.y) - (xx + yy);
+        let yz = rhs.y * self.z + self.y;
+        let xz = rhs.x * self.z + self.x;
+
+        Self::combine(xx, yy, self.z, xy, yz, xz)
+    }
+
+    // Computes the result from the products X1X2, Y1Y2, Z1Z2 and the sums of
+    // the mixed products X1Y2 + X2Y1, Y1Z2 + Y2Z1 and X1Z2 + X2Z1.
+    fn combine(
+        xx: field::Element,
+        yy: field::Element,
+        zz: field::Element,
+        xy: field::Element,
+        yz: field::Element,
+        xz: field::Element,
+    ) -> Self {
+        let bzz3 = zz * 21.into();
+        let yy_minus_bzz3 = yy - bzz3;
+        let yy_plus_bzz3 = yy + bzz3;
+
+        let byz3 = yz * 21.into();
+        let xx3 = xx * 3.into();
+        let bxx9 = xx * 63.into();
+
+        Self {
+            x: (xy * yy_minus_bzz3 - byz3 * xz).reduce(),
+            y: (yy_plus_bzz3 * yy_minus_bzz3 + bxx9 * xz).reduce(),
+            z: (yz * yy_plus_bzz3 + xx3 * xy).reduce(),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -173,6 +415,108 @@ mod tests {
         }
     }
 
+    #[test]
+    fn projective_add() {
+        let g = super::ProjectivePoint::NEUTRAL.add_affine(super::AffinePoint::GENERATOR);
+
+        let mut a = super::ProjectivePoint::NEUTRAL;
+        let mut b = super::Point::E;
+
+        for _ in 0..32 {
+            a = a.add(g);
+            b = b.add(super::GENERATOR);
+
+            let p = a.add(a).to_point();
+            let q = b.double();
+
+            p.verify();
+
+            assert!(p.affine_x() == q.affine_x());
+            assert!(p.affine_y() == q.affine_y());
+        }
+
+        let minus_a = super::ProjectivePoint {
+            y: a.y.negative(),
+            ..a
+        };
+
+        assert!(a.add(minus_a).to_point().is_neutral());
+        assert!(super::ProjectivePoint::NEUTRAL
+            .add(super::ProjectivePoint::NEUTRAL)
+            .to_point()
+            .is_neutral());
+    }
+
+    #[test]
+    fn add_affine() {
+        let g = super::AffinePoint::GENERATOR;
+
+        let mut a = super::Point::E;
+        let mut b = super::ProjectivePoint::NEUTRAL;
+
+        for _ in 0..32 {
+            a = a.add_affine(g);
+            b = b.add_affine(g);
+
+            a.verify();
+
+            let p = a.add_affine(a.to_affine());
+            let q = b.add(b).to_point();
+
+            assert!(p.affine_x() == q.affine_x());
+            assert!(p.affine_y() == q.affine_y());
+        }
+
+        assert!(a.add_affine(a.to_affine().negative()).is_neutral());
+        assert!(super::ProjectivePoint::NEUTRAL
+            .add_affine(g.negative())
+            .add_affine(g)
+            .to_point()
+            .is_neutral());
+    }
+
+    #[test]
+    fn lift_x() {
+        let g = super::AffinePoint::GENERATOR;
+
+        let even = super::AffinePoint::lift_x(g.x, false).unwrap();
+        let odd = super::AffinePoint::lift_x(g.x, true).unwrap();
+
+        assert!(even.y == g.y);
+        assert!(odd.y == g.y.negative());
+        assert!(even.is_on_curve() && odd.is_on_curve());
+
+        // x^3 + 7 = 132 is not a square modulo p
+        assert!(super::AffinePoint::lift_x(5.into(), false).is_none());
+        assert!(!super::AffinePoint { x: g.x, y: g.x }.is_on_curve());
+    }
+
+    #[test]
+    fn batch_to_affine() {
+        let mut points = vec![super::GENERATOR];
+
+        for i in 0..16 {
+            points.push(if i % 2 == 0 {
+                points[i].double()
+            } else {
+                points[i].add(super::GENERATOR)
+            });
+        }
+
+        let affine = super::batch_to_affine(&points);
+
+        assert!(affine.len() == points.len());
+
+        for (a, p) in affine.into_iter().zip(points) {
+            let b = p.to_affine();
+
+            assert!(a.x == b.x);
+            assert!(a.y == b.y);
+        }
+
+        assert!(super::batch_to_affine(&[]).is_empty());
+    }
+
     #[test]
     fn little_fermat() {
         const CURVE_ORDER: [u8; 32] = hex_literal::hex!(
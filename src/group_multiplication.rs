@@ -0,0 +1,563 @@
+use super::field;
+use super::group;
+use super::scalar;
+
+// GENERATOR_TABLE[i][j] holds the affine coordinates of (j + 1) * 16^i * G
+// and is generated by the build script.
+include!(concat!(env!("OUT_DIR"), "/generator_table.rs"));
+
+// beta is a cube root of unity modulo p such that (beta * x, y) = lambda * (x, y)
+const BETA: field::Element = field::Element {
+    limbs: [
+        0x96C28719501EE,
+        0x7512F58995C13,
+        0xC3434E99CF049,
+        0x7106E64479EA,
+        0x7AE96A2B657C,
+    ],
+    magnitude: 0,
+};
+
+const LAMBDA: [u8; 32] = hex_literal::hex!(
+    "5363AD4CC05C30E0A5261C028812645A"
+    "122E22EA20816678DF02967C1B23BD72"
+);
+
+// The constants for the decomposition of a scalar are taken from libsecp256k1,
+// where b1 and b2 are short vectors of the lattice {(a, b) | a + b * lambda = 0}
+// and g1 = round(2^384 * b2 / d), g2 = round(2^384 * (-b1) / d).
+const MINUS_B1: [u8; 32] = hex_literal::hex!(
+    "00000000000000000000000000000000"
+    "E4437ED6010E88286F547FA90ABFE4C3"
+);
+
+const MINUS_B2: [u8; 32] = hex_literal::hex!(
+    "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFE"
+    "8A280AC50774346DD765CDA83DB1562C"
+);
+
+const G1: [u8; 32] = hex_literal::hex!(
+    "3086D221A7D46BCDE86C90E49284EB15"
+    "3DAA8A1471E8CA7FE893209A45DBB031"
+);
+
+const G2: [u8; 32] = hex_literal::hex!(
+    "E4437ED6010E88286F547FA90ABFE4C4"
+    "221208AC9DF506C61571B4AE8AC47F71"
+);
+
+// Below this number of terms Strauss' algorithm is faster than Pippenger's.
+// Both are measured by the verification benchmark, the crossover lies at
+// about 190 terms, which is a batch of 95 signatures.
+const PIPPENGER_THRESHOLD: usize = 192;
+
+// The default width of the wNAF recoding used for variable base multiplication,
+// which requires a table of 2^(w - 2) odd multiples per point.
+pub const WNAF_WIDTH: usize = 5;
+
+// Returns the bits [offset, offset + width) of a big endian scalar, where bit
+// zero is the least significant bit.
+fn window(scalar: &[u8; 32], offset: usize, width: usize) -> usize {
+    (offset..offset + width)
+        .filter(|&i| i < 256)
+        .map(|i| ((scalar[31 - i / 8] >> (i % 8)) & 1) as usize)
+        .enumerate()
+        .fold(0, |w, (j, b)| w | (b << j))
+}
+
+// Recodes a scalar into its width w non-adjacent form with the least significant
+// digit first. Every digit is either zero or odd and in the range
+// (-2^(w - 1), 2^(w - 1)), and any w consecutive digits contain at most one
+// nonzero digit.
+pub fn wnaf(scalar: [u8; 32], width: usize) -> Vec<i32> {
+    assert!((2..=16).contains(&width));
+
+    // little endian limbs with an additional limb for the carry of negative digits
+    let mut k = [0u64; 5];
+
+    for (i, l) in k[..4].iter_mut().enumerate() {
+        *l = u64::from_be_bytes(scalar[24 - 8 * i..32 - 8 * i].try_into().unwrap());
+    }
+
+    let mut digits = Vec::with_capacity(257);
+
+    while k != [0; 5] {
+        let mut d = 0;
+
+        if k[0] & 1 == 1 {
+            d = (k[0] & ((1 << width) - 1)) as i32;
+
+            if d >= 1 << (width - 1) {
+                d -= 1 << width;
+            }
+
+            // Subtracting d clears the lowest w bits of k, as d = k mod 2^w.
+            let mut carry = -d as i128;
+
+            for l in k.iter_mut() {
+                let t = *l as i128 + carry;
+                *l = t as u64;
+                carry = t >> 64;
+            }
+
+            assert!(carry == 0);
+        }
+
+        digits.push(d);
+
+        for i in 0..4 {
+            k[i] = (k[i] >> 1) | (k[i + 1] << 63);
+        }
+
+        k[4] >>= 1;
+    }
+
+    digits
+}
+
+// Returns the odd multiples P, 3P, 5P, ..., (2^(w - 1) - 1)P. Let Z be the z
+// coordinate of 2P = (X, Y, Z), then on the isomorphic curve y^2 = x^3 + 7Z^6
+// the point 2P has the affine coordinates (X, Y) and P has (xZ^2, yZ^3), such
+// that the table can be computed there with mixed additions. Multiplying the
+// resulting z coordinates by Z maps the points back to our curve.
+fn odd_multiples(point: group::AffinePoint, width: usize) -> Vec<group::Point> {
+    let group::Point::P(dx, dy, dz) = point.to_point().double() else {
+        unreachable!("the curve has no points of order two");
+    };
+
+    let double = group::AffinePoint { x: dx, y: dy };
+    let dz2 = dz.square();
+
+    let mut p = group::Point::P(point.x * dz2, point.y * dz2 * dz, field::ONE);
+    let mut table = vec![point.to_point()];
+
+    for _ in 1..1 << (width - 2) {
+        p = p.add_affine(double);
+
+        let group::Point::P(x, y, z) = p else {
+            unreachable!("small multiples of a point are not neutral");
+        };
+
+        table.push(group::Point::P(x, y, z * dz));
+    }
+
+    table
+}
+
+// Recodes a scalar into 65 signed digits in [-8, 8) with the least significant
+// digit first, such that the scalar is the sum of all d_i * 16^i.
+fn signed_digits(scalar: [u8; 32]) -> [i32; 65] {
+    let mut digits = [0; 65];
+    let mut carry = 0;
+
+    for (i, digit) in digits.iter_mut().enumerate() {
+        let d = window(&scalar, 4 * i, 4) as i32 + carry;
+
+        // d is at most 16, we carry to the next digit if d is at least 8
+        carry = (d + 8) >> 4;
+        *digit = d - (carry << 4);
+    }
+
+    assert!(carry == 0);
+
+    digits
+}
+
+// Multiplies the generator by a secret scalar in constant time. For every
+// window all table entries are scanned with conditional moves and the
+// selected point is conditionally negated and added with a mixed formula,
+// so neither the memory access pattern nor the control flow depends on the
+// scalar.
+pub fn multiply_generator(scalar: [u8; 32]) -> group::ProjectivePoint {
+    let mut g = group::ProjectivePoint::NEUTRAL;
+
+    for (table, d) in GENERATOR_TABLE.iter().zip(signed_digits(scalar)) {
+        let mask = d >> 31;
+        let abs = ((d ^ mask) - mask) as usize;
+
+        let mut point = group::AffinePoint {
+            x: field::ZERO,
+            y: field::ZERO,
+        };
+
+        for (j, &(x, y)) in table.iter().enumerate() {
+            let entry = group::AffinePoint {
+                x: field::Element {
+                    limbs: x,
+                    magnitude: 0,
+                },
+                y: field::Element {
+                    limbs: y,
+                    magnitude: 0,
+                },
+            };
+
+            point = point.cmov(entry, abs == j + 1);
+        }
+
+        // The mixed addition can not add the neutral element, so for a zero
+        // digit the sum is computed with a dummy point and discarded.
+        let sum = g.add_affine(point.negate_if(mask != 0));
+        g = g.cmov(sum, abs != 0);
+    }
+
+    g
+}
+
+// Splits k into k1 and k2 such that k = k1 + k2 * lambda mod n, where k1 and
+// k2 or their negatives are less than 2^128.
+pub fn split_scalar(k: scalar::Scalar) -> (scalar::Scalar, scalar::Scalar) {
+    let c1 = k.multiply_shift_384(scalar::Scalar::decode(G1));
+    let c2 = k.multiply_shift_384(scalar::Scalar::decode(G2));
+
+    let k2 = c1 * scalar::Scalar::decode(MINUS_B1) + c2 * scalar::Scalar::decode(MINUS_B2);
+    let k1 = k - k2 * scalar::Scalar::decode(LAMBDA);
+
+    (k1, k2)
+}
+
+fn endomorphism(point: group::AffinePoint) -> group::AffinePoint {
+    group::AffinePoint {
+        x: BETA * point.x,
+        y: point.y,
+    }
+}
+
+// Replaces the term k * P with k1 * P + k2 * lambda(P), where the points are
+// negated as needed such that both scalars are less than 2^128.
+fn split_term(
+    (point, scalar): (group::AffinePoint, [u8; 32]),
+) -> [(group::AffinePoint, [u8; 32]); 2] {
+    let (k1, k2) = split_scalar(scalar::Scalar::decode(scalar));
+
+    [(point, k1), (endomorphism(point), k2)].map(|(point, k)| {
+        let (point, k) = if k.is_high() {
+            (point.negative(), -k)
+        } else {
+            (point, k)
+        };
+
+        let k = k.encode();
+        assert!(k[..16] == [0; 16]);
+
+        (point, k)
+    })
+}
+
+// Computes the sum of all terms with half the number of doublings by
+// splitting every scalar with the endomorphism.
+pub fn multiply_multi_endomorphism(
+    terms: &[(group::AffinePoint, [u8; 32])],
+    width: usize,
+) -> group::Point {
+    let terms: Vec<(group::AffinePoint, [u8; 32])> =
+        terms.iter().copied().flat_map(split_term).collect();
+
+    strauss(&terms, width)
+}
+
+pub fn multiply_multi(terms: &[(group::AffinePoint, [u8; 32])]) -> group::Point {
+    if terms.len() < PIPPENGER_THRESHOLD {
+        strauss(terms, WNAF_WIDTH)
+    } else {
+        pippenger(terms)
+    }
+}
+
+// Computes the sum of all terms with interleaved wNAF multiplications.
+pub fn strauss(terms: &[(group::AffinePoint, [u8; 32])], width: usize) -> group::Point {
+    let multiples: Vec<group::Point> = terms
+        .iter()
+        .flat_map(|(point, _)| odd_multiples(*point, width))
+        .collect();
+
+    // All tables are converted to affine coordinates at the cost of a single
+    // inversion, such that the additions below can use the mixed formula.
+    let multiples = group::batch_to_affine(&multiples);
+    let tables: Vec<&[group::AffinePoint]> = multiples.chunks(1 << (width - 2)).collect();
+
+    let digits: Vec<Vec<i32>> = terms
+        .iter()
+        .map(|(_, scalar)| wnaf(*scalar, width))
+        .collect();
+
+    let length = digits.iter().map(Vec::len).max().unwrap_or(0);
+
+    let mut g = group::Point::E;
+
+    // All terms share the doublings, only the additions are done per term.
+    for i in (0..length).rev() {
+        g = g.double();
+
+        for (digits, table) in digits.iter().zip(&tables) {
+            match digits.get(i) {
+                Some(&d) if d > 0 => g = g.add_affine(table[(d / 2) as usize]),
+                Some(&d) if d < 0 => g = g.add_affine(table[(-d / 2) as usize].negative()),
+                _ => {}
+            }
+        }
+    }
+
+    g
+}
+
+pub fn pippenger(terms: &[(group::AffinePoint, [u8; 32])]) -> group::Point {
+    // The window width c balances the 2^c bucket additions per window
+    // against the n additions of points into buckets per window.
+    let c = (usize::BITS - terms.len().leading_zeros()) as usize - 2;
+
+    let mut g = group::Point::E;
+
+    for offset in (0..256).step_by(c).rev() {
+        for _ in 0..c {
+            g = g.double();
+        }
+
+        let mut buckets = vec![group::Point::E; (1 << c) - 1];
+
+        for (point, scalar) in terms {
+            let w = window(scalar, offset, c);
+
+            if w != 0 {
+                buckets[w - 1] = buckets[w - 1].add_affine(*point);
+            }
+        }
+
+        // The running sum adds bucket j to the total j + 1 times, such that
+        // the total is the sum of all (j + 1) * B_j.
+        let mut running = group::Point::E;
+        let mut total = group::Point::E;
+
+        for bucket in buckets.into_iter().rev() {
+            running = running.add(bucket);
+            total = total.add(running);
+        }
+
+        g = g.add(total);
+    }
+
+    g
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::group;
+    use crate::scalar;
+    use crate::schnorr;
+
+    fn terms(n: usize) -> Vec<(group::AffinePoint, [u8; 32])> {
+        let mut point = group::GENERATOR;
+        let mut scalar = [0xA5u8; 32];
+
+        (0..n)
+            .map(|i| {
+                point = point.double().add(group::GENERATOR);
+                scalar[i % 32] ^= scalar[(7 * i + 3) % 32].rotate_left(3) ^ i as u8;
+
+                (point.to_affine(), scalar)
+            })
+            .collect()
+    }
+
+    fn multiply_naive(terms: &[(group::AffinePoint, [u8; 32])]) -> group::Point {
+        terms
+            .iter()
+            .map(|(point, scalar)| schnorr::multiply_by_scalar(point.to_point(), *scalar))
+            .fold(group::Point::E, group::Point::add)
+    }
+
+    fn assert_equal(a: group::Point, b: group::Point) {
+        assert!(a.is_neutral() == b.is_neutral());
+
+        if a.is_neutral() {
+            return;
+        }
+
+        assert!(a.affine_x() == b.affine_x());
+        assert!(a.affine_y() == b.affine_y());
+    }
+
+    #[test]
+    fn multiply_generator() {
+        for (_, scalar) in terms(8) {
+            assert_equal(
+                super::multiply_generator(scalar).to_point(),
+                schnorr::multiply_by_scalar(group::GENERATOR, scalar),
+            );
+        }
+
+        assert!(super::multiply_generator([0; 32]).to_point().is_neutral());
+    }
+
+    #[test]
+    fn signed_digits() {
+        for (_, k) in terms(8) {
+            let mut sum = scalar::Scalar::ZERO;
+
+            for d in super::signed_digits(k).into_iter().rev() {
+                let d_abs = scalar::Scalar::from(d.unsigned_abs() as u64);
+                sum = sum * 16.into() + if d < 0 { -d_abs } else { d_abs };
+            }
+
+            assert!(sum == scalar::Scalar::decode(k));
+        }
+    }
+
+    // Statistical timing test in the style of dudect, which compares the
+    // execution times of multiply_generator for a fixed scalar and random
+    // scalars with Welch's t-test. Run it in release mode with
+    // cargo test --release -- --ignored constant_time
+    #[test]
+    #[ignore]
+    fn constant_time_multiply_generator() {
+        const MEASUREMENTS: usize = 20_000;
+
+        let mut fixed = Vec::new();
+        let mut random = Vec::new();
+
+        for i in 0..MEASUREMENTS {
+            let mut hasher = crate::hash::Sha256::empty();
+            hasher.write(&(i as u64).to_be_bytes());
+            let randomness = hasher.finish();
+
+            let is_fixed = randomness[0] & 1 == 0;
+            // a scalar of one consists almost entirely of zero digits
+            let scalar = if is_fixed {
+                scalar::Scalar::ONE.encode()
+            } else {
+                randomness
+            };
+
+            let start = std::time::Instant::now();
+            std::hint::black_box(super::multiply_generator(std::hint::black_box(scalar)));
+            let elapsed = start.elapsed().as_nanos() as f64;
+
+            if is_fixed {
+                fixed.push(elapsed);
+            } else {
+                random.push(elapsed);
+            }
+        }
+
+        // discard the slowest measurements, which are dominated by interrupts
+        let mut all: Vec<f64> = fixed.iter().chain(&random).copied().collect();
+        all.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let cutoff = all[all.len() * 9 / 10];
+
+        let statistics = |samples: &[f64]| {
+            let samples: Vec<f64> = samples.iter().copied().filter(|&t| t < cutoff).collect();
+            let n = samples.len() as f64;
+            let mean = samples.iter().sum::<f64>() / n;
+            let variance = samples.iter().map(|t| (t - mean).powi(2)).sum::<f64>() / (n - 1.0);
+
+            (n, mean, variance)
+        };
+
+        let (n_a, mean_a, var_a) = statistics(&fixed);
+        let (n_b, mean_b, var_b) = statistics(&random);
+
+        let t = (mean_a - mean_b) / (var_a / n_a + var_b / n_b).sqrt();
+
+        // dudect considers |t| > 10 as a definite timing leak
+        assert!(t.abs() < 10.0, "t-statistic {} indicates a timing leak", t);
+    }
+
+    #[test]
+    fn strauss() {
+        let terms = terms(5);
+
+        for width in 2..8 {
+            assert_equal(super::strauss(&terms, width), multiply_naive(&terms));
+        }
+
+        assert!(super::strauss(&[], super::WNAF_WIDTH).is_neutral());
+    }
+
+    #[test]
+    fn pippenger() {
+        let terms = terms(super::PIPPENGER_THRESHOLD + 3);
+
+        assert_equal(super::pippenger(&terms), multiply_naive(&terms));
+    }
+
+    #[test]
+    fn wnaf() {
+        for (_, k) in terms(8) {
+            for width in 2..8 {
+                let digits = super::wnaf(k, width);
+
+                let mut sum = scalar::Scalar::ZERO;
+
+                for &d in digits.iter().rev() {
+                    let d_abs = scalar::Scalar::from(d.unsigned_abs() as u64);
+                    sum = sum + sum + if d < 0 { -d_abs } else { d_abs };
+                }
+
+                assert!(sum == scalar::Scalar::decode(k));
+
+                for (i, &d) in digits.iter().enumerate() {
+                    assert!(d % 2 != 0 || d == 0);
+                    assert!(d.abs() < 1 << (width - 1));
+
+                    if d != 0 {
+                        assert!(digits[i + 1..].iter().take(width - 1).all(|&d| d == 0));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn split_scalar() {
+        let lambda = scalar::Scalar::decode(super::LAMBDA);
+
+        for (_, k) in terms(32) {
+            let k = scalar::Scalar::decode(k);
+            let (k1, k2) = super::split_scalar(k);
+
+            assert!(k1 + k2 * lambda == k);
+
+            for k in [k1, k2] {
+                let k = if k.is_high() { -k } else { k };
+                assert!(k.encode()[..16] == [0; 16]);
+            }
+        }
+    }
+
+    #[test]
+    fn multiply_endomorphism() {
+        let minus_one = scalar::Scalar::ONE.negative().encode();
+        let lambda = super::LAMBDA;
+
+        for (point, k) in terms(8).into_iter().chain([
+            (group::AffinePoint::GENERATOR, [0; 32]),
+            (group::AffinePoint::GENERATOR, minus_one),
+            (group::AffinePoint::GENERATOR, lambda),
+        ]) {
+            assert_equal(
+                super::multiply_multi_endomorphism(&[(point, k)], super::WNAF_WIDTH),
+                schnorr::multiply_by_scalar(point.to_point(), k),
+            );
+        }
+    }
+
+    #[test]
+    fn multiply_multi_endomorphism() {
+        let terms = terms(5);
+
+        assert_equal(
+            super::multiply_multi_endomorphism(&terms, super::WNAF_WIDTH),
+            multiply_naive(&terms),
+        );
+    }
+
+    #[test]
+    fn cancellation() {
+        let mut terms = terms(3);
+        terms.push((terms[0].0.negative(), terms[0].1));
+
+        let expected = multiply_naive(&terms[1..3]);
+
+        assert_equal(super::multiply_multi(&terms), expected);
+    }
+}
@@ -1,4 +1,7 @@
-const K: [u32; 64] = [
+#[cfg(target_arch = "x86_64")]
+use super::hash_x86_64;
+
+pub const K: [u32; 64] = [
     0x428A2F98, 0x71374491, 0xB5C0FBCF, 0xE9B5DBA5, 0x3956C25B, 0x59F111F1, 0x923F82A4, 0xAB1C5ED5,
     0xD807AA98, 0x12835B01, 0x243185BE, 0x550C7DC3, 0x72BE5D74, 0x80DEB1FE, 0x9BDC06A7, 0xC19BF174,
     0xE49B69C1, 0xEFBE4786, 0xFC19DC6, 0x240CA1CC, 0x2DE92C6F, 0x4A7484AA, 0x5CB0A9DC, 0x76F988DA,
@@ -13,6 +16,66 @@ const H: [u32; 8] = [
     0x6A09E667, 0xBB67AE85, 0x3C6EF372, 0xA54FF53A, 0x510E527F, 0x9B05688C, 0x1F83D9AB, 0x5BE0CD19,
 ];
 
+// A hash function with a Merkle-Damgard construction, over which keyed
+// constructions like HMAC are generic.
+pub trait HashFunction: Clone {
+    const BLOCK_SIZE: usize;
+    const OUTPUT_SIZE: usize;
+
+    type Digest: AsRef<[u8]>;
+
+    fn empty() -> Self;
+    fn write(&mut self, bytes: &[u8]);
+    fn finish(self) -> Self::Digest;
+}
+
+// The state of the hash function after a number of bytes that is a multiple of
+// the block size, from which hashing can be resumed. The length is in bytes and
+// is kept below 2^61, such that the length in bits fits into the padding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Midstate {
+    state: [u32; 8],
+    length: u64,
+}
+
+impl Midstate {
+    // Encodes the state words followed by the length in bytes, all big endian.
+    pub fn to_bytes(self) -> [u8; 40] {
+        let mut bytes = [0; 40];
+
+        for (chunk, word) in bytes.chunks_mut(4).zip(self.state) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+
+        bytes[32..].copy_from_slice(&self.length.to_be_bytes());
+        bytes
+    }
+
+    // Decodes a midstate, or returns None if the length is not a multiple of 64
+    // or too large.
+    pub fn from_bytes(bytes: [u8; 40]) -> Option<Self> {
+        let state = core::array::from_fn(|i| {
+            u32::from_be_bytes(bytes[4 * i..4 * i + 4].try_into().unwrap())
+        });
+        let length = u64::from_be_bytes(bytes[32..].try_into().unwrap());
+
+        if length % 64 != 0 || length >= 1 << 61 {
+            return None;
+        }
+
+        Some(Self { state, length })
+    }
+
+    pub fn state(self) -> [u32; 8] {
+        self.state
+    }
+
+    pub fn length(self) -> u64 {
+        self.length
+    }
+}
+
+#[derive(Clone)]
 pub struct Sha256 {
     state: [u32; 8],
     buffer: [u8; 64],
@@ -53,6 +116,52 @@ impl Sha256 {
         self.n_buffer += bytes.len();
     }
 
+    // Hashes many independent messages, eight at a time in the lanes of AVX2
+    // registers when the CPU supports it. Messages hashed together take as
+    // many compressions as the longest of them, so this pays off for many
+    // short messages of similar length. The SHA extensions compress a single
+    // block faster than AVX2 compresses eight, so they take precedence.
+    pub fn hash_many(messages: &[&[u8]]) -> Vec<[u8; 32]> {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("avx2") && !is_x86_feature_detected!("sha") {
+                return messages.chunks(8).flat_map(hash_eight).collect();
+            }
+        }
+
+        messages
+            .iter()
+            .map(|message| {
+                let mut hasher = Self::empty();
+                hasher.write(message);
+                hasher.finish()
+            })
+            .collect()
+    }
+
+    // Resumes hashing from a midstate.
+    pub fn from_midstate(midstate: Midstate) -> Self {
+        Self {
+            state: midstate.state,
+            buffer: [0u8; 64],
+            n_buffer: 0,
+            n_rounds: midstate.length / 64,
+        }
+    }
+
+    // Exports the state, which is only possible after a multiple of 64 bytes
+    // has been written, otherwise None is returned.
+    pub fn midstate(&self) -> Option<Midstate> {
+        if self.n_buffer != 0 {
+            return None;
+        }
+
+        Some(Midstate {
+            state: self.state,
+            length: self.n_rounds * 64,
+        })
+    }
+
     pub fn finish(mut self) -> [u8; 32] {
         let n_written_bits = self.n_rounds * 512 + (self.n_buffer as u64) * 8;
         let n_padding = if self.n_buffer < 56 { 55 } else { 119 } - self.n_buffer;
@@ -68,7 +177,129 @@ impl Sha256 {
     }
 }
 
+impl HashFunction for Sha256 {
+    const BLOCK_SIZE: usize = 64;
+    const OUTPUT_SIZE: usize = 32;
+
+    type Digest = [u8; 32];
+
+    fn empty() -> Self {
+        Sha256::empty()
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        Sha256::write(self, bytes)
+    }
+
+    fn finish(self) -> [u8; 32] {
+        Sha256::finish(self)
+    }
+}
+
+// Hashes as in BIP340, SHA256(SHA256(tag) || SHA256(tag) || data). The prefix
+// fills exactly one block, so the hasher after it is computed once and cloned
+// for every message.
+#[derive(Clone)]
+pub struct TaggedHash {
+    hasher: Sha256,
+}
+
+impl TaggedHash {
+    pub fn new(tag: &str) -> Self {
+        let mut hasher = Sha256::empty();
+        hasher.write(tag.as_bytes());
+        let tag_hash = hasher.finish();
+
+        let mut hasher = Sha256::empty();
+        hasher.write(&tag_hash);
+        hasher.write(&tag_hash);
+
+        assert!(hasher.n_buffer == 0);
+
+        Self { hasher }
+    }
+
+    // Returns a hasher with the tag prefix already written.
+    pub fn hasher(&self) -> Sha256 {
+        self.hasher.clone()
+    }
+
+    pub fn hash(&self, data: &[u8]) -> [u8; 32] {
+        let mut hasher = self.hasher();
+        hasher.write(data);
+        hasher.finish()
+    }
+}
+
+// Appends the padding of the final block, after which the length is a multiple
+// of 64.
+#[cfg(target_arch = "x86_64")]
+fn pad(message: &[u8]) -> Vec<u8> {
+    let n_written_bits = (message.len() as u64) * 8;
+    let n_padding = (119 - message.len() % 64) % 64;
+
+    let mut padded = message.to_vec();
+    padded.push(128);
+    padded.extend_from_slice(&[0; 63][..n_padding]);
+    padded.extend_from_slice(&n_written_bits.to_be_bytes());
+
+    assert!(padded.len() & 63 == 0);
+
+    padded
+}
+
+// Hashes up to eight messages in parallel. Lanes whose message has no more
+// blocks are compressed with a dummy block and their result is discarded.
+#[cfg(target_arch = "x86_64")]
+fn hash_eight(messages: &[&[u8]]) -> Vec<[u8; 32]> {
+    assert!(messages.len() <= 8);
+
+    let padded: Vec<Vec<u8>> = messages.iter().map(|message| pad(message)).collect();
+    let n_blocks = padded.iter().map(|p| p.len() / 64).max().unwrap_or(0);
+
+    let mut states = [H; 8];
+    let dummy = [0u8; 64];
+
+    for i in 0..n_blocks {
+        let blocks = core::array::from_fn(|j| match padded.get(j) {
+            Some(p) if 64 * i < p.len() => p[64 * i..64 * i + 64].try_into().unwrap(),
+            _ => &dummy,
+        });
+
+        let mut next = states;
+        unsafe { hash_x86_64::update_states_avx2(&mut next, blocks) };
+
+        for (j, p) in padded.iter().enumerate() {
+            if 64 * i < p.len() {
+                states[j] = next[j];
+            }
+        }
+    }
+
+    states[..messages.len()]
+        .iter()
+        .map(|state| unsafe { std::mem::transmute::<[u32; 8], [u8; 32]>(state.map(u32::to_be)) })
+        .collect()
+}
+
+// Compresses one block with the fastest implementation supported by the CPU.
+// The feature detection is cached by the standard library after the first call.
 fn update_state(state: &mut [u32; 8], bytes: &[u8; 64]) {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("sha") && is_x86_feature_detected!("sse4.1") {
+            return unsafe { hash_x86_64::update_state_sha(state, bytes) };
+        }
+
+        if is_x86_feature_detected!("ssse3") {
+            return unsafe { hash_x86_64::update_state_ssse3(state, bytes) };
+        }
+    }
+
+    update_state_portable(state, bytes)
+}
+
+pub fn update_state_portable(state: &mut [u32; 8], bytes: &[u8; 64]) {
     let mut w: [u32; 64] = [0; 64];
     let byte_blocks = unsafe { std::mem::transmute::<&[u8; 64], &[[u8; 4]; 16]>(bytes) };
     let words: [u32; 16] = byte_blocks.map(u32::from_be_bytes);
@@ -83,6 +314,11 @@ fn update_state(state: &mut [u32; 8], bytes: &[u8; 64]) {
             .wrapping_add(s1);
     }
 
+    rounds(state, &w);
+}
+
+// The 64 rounds of the compression function for an expanded message schedule.
+pub fn rounds(state: &mut [u32; 8], w: &[u32; 64]) {
     let mut h = *state;
     for i in 0..64 {
         let ch = (h[4] & h[5]) ^ (!h[4] & h[6]);
@@ -158,8 +394,98 @@ mod tests {
         assert!(digest == hash);
     }
 
+    #[test]
+    fn tagged_hash() {
+        let tag_hash = hex_literal::hex!(
+            "7BB52D7A9FEF58323EB1BF7A407DB382"
+            "D2F3F2D81BB1224F49FE518F6D48D37C"
+        );
+
+        let mut prefix = tag_hash.to_vec();
+        prefix.extend_from_slice(&tag_hash);
+
+        let tagged_hash = super::TaggedHash::new("BIP0340/challenge");
+
+        for length in [0, 1, 32, 64, 100] {
+            let data = vec![0xA5; length];
+            let expected = digest(&[&prefix[..], &data[..]].concat());
+
+            assert!(tagged_hash.hash(&data) == expected);
+        }
+    }
+
+    #[test]
+    fn midstate() {
+        let data = [0x3C; 200];
+        let expected = digest(&data);
+
+        assert!(super::Sha256::empty().midstate().unwrap().state() == super::H);
+
+        for n_blocks in 0..4 {
+            let mut sha256 = super::Sha256::empty();
+            sha256.write(&data[..64 * n_blocks]);
+
+            let midstate = sha256.midstate().unwrap();
+            assert!(midstate.length() == 64 * n_blocks as u64);
+
+            let midstate = super::Midstate::from_bytes(midstate.to_bytes()).unwrap();
+            let mut sha256 = super::Sha256::from_midstate(midstate);
+            sha256.write(&data[64 * n_blocks..]);
+
+            assert!(sha256.finish() == expected);
+        }
+
+        let mut sha256 = super::Sha256::empty();
+        sha256.write(&data[..65]);
+        assert!(sha256.midstate().is_none());
+
+        let mut bytes = [0; 40];
+        bytes[39] = 65;
+        assert!(super::Midstate::from_bytes(bytes).is_none());
+        assert!(super::Midstate::from_bytes([1; 40]).is_none());
+
+        bytes[32] = 0x20;
+        bytes[39] = 0;
+        assert!(super::Midstate::from_bytes(bytes).is_none());
+    }
+
+    #[test]
+    fn hash_many() {
+        let data: Vec<u8> = (0..1000).map(|i| (i * 7 + 3) as u8).collect();
+
+        // lengths around the padding boundaries, with groups of eight and a
+        // partial group at the end
+        let messages: Vec<&[u8]> = [0, 1, 55, 56, 63, 64, 65, 119, 120, 128, 300, 999, 3]
+            .iter()
+            .map(|&n| &data[..n])
+            .collect();
+
+        let digests = super::Sha256::hash_many(&messages);
+
+        assert!(digests.len() == messages.len());
+
+        for (message, hash) in messages.iter().zip(digests) {
+            assert!(digest(message) == hash);
+        }
+
+        assert!(super::Sha256::hash_many(&[]).is_empty());
+
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("avx2") {
+                let digests: Vec<[u8; 32]> =
+                    messages.chunks(8).flat_map(super::hash_eight).collect();
+
+                for (message, hash) in messages.iter().zip(digests) {
+                    assert!(digest(message) == hash);
+                }
+            }
+        }
+    }
+
     /// Run large input test (1,000,000 x a) from FIPS 180-2
     #[test]
+    #[allow(clippy::manual_str_repeat, clippy::manual_repeat_n)]
     fn sha256_nist_large_input() {
         let input_str = std::iter::repeat("a").take(1_000_000).collect::<String>();
         let digest = digest(input_str.as_bytes());
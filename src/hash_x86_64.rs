@@ -0,0 +1,307 @@
+use super::hash;
+
+use std::arch::x86_64::*;
+
+// Reverses the bytes within each 32 bit lane to load big endian words.
+const BYTE_SWAP: (i64, i64) = (0x0C0D_0E0F_0809_0A0B, 0x0405_0607_0001_0203);
+
+// Compresses one block with the SHA extensions. The instructions keep the
+// state in the registers as (a, b, e, f) and (c, d, g, h), and each
+// sha256rnds2 performs two rounds with the message words and constants in the
+// lower half of its third operand.
+#[target_feature(enable = "sha,sse2,ssse3,sse4.1")]
+pub fn update_state_sha(state: &mut [u32; 8], bytes: &[u8; 64]) {
+    let mask = _mm_set_epi64x(BYTE_SWAP.0, BYTE_SWAP.1);
+
+    let dcba = unsafe { _mm_loadu_si128(state.as_ptr() as *const __m128i) };
+    let hgfe = unsafe { _mm_loadu_si128(state.as_ptr().add(4) as *const __m128i) };
+
+    let cdab = _mm_shuffle_epi32(dcba, 0xB1);
+    let efgh = _mm_shuffle_epi32(hgfe, 0x1B);
+    let mut abef = _mm_alignr_epi8(cdab, efgh, 8);
+    let mut cdgh = _mm_blend_epi16(efgh, cdab, 0xF0);
+
+    let abef_start = abef;
+    let cdgh_start = cdgh;
+
+    // a sliding window over the message schedule, four words per register
+    let mut w = [_mm_setzero_si128(); 4];
+
+    for (i, w) in w.iter_mut().enumerate() {
+        let block = unsafe { _mm_loadu_si128(bytes.as_ptr().add(16 * i) as *const __m128i) };
+        *w = _mm_shuffle_epi8(block, mask);
+    }
+
+    for i in 0..16 {
+        if i >= 4 {
+            let t = _mm_sha256msg1_epu32(w[i % 4], w[(i + 1) % 4]);
+            let t = _mm_add_epi32(t, _mm_alignr_epi8(w[(i + 3) % 4], w[(i + 2) % 4], 4));
+            w[i % 4] = _mm_sha256msg2_epu32(t, w[(i + 3) % 4]);
+        }
+
+        let k = unsafe { _mm_loadu_si128(hash::K.as_ptr().add(4 * i) as *const __m128i) };
+        let wk = _mm_add_epi32(w[i % 4], k);
+
+        cdgh = _mm_sha256rnds2_epu32(cdgh, abef, wk);
+        abef = _mm_sha256rnds2_epu32(abef, cdgh, _mm_shuffle_epi32(wk, 0x0E));
+    }
+
+    abef = _mm_add_epi32(abef, abef_start);
+    cdgh = _mm_add_epi32(cdgh, cdgh_start);
+
+    let feba = _mm_shuffle_epi32(abef, 0x1B);
+    let dchg = _mm_shuffle_epi32(cdgh, 0xB1);
+    let dcba = _mm_blend_epi16(feba, dchg, 0xF0);
+    let hgfe = _mm_alignr_epi8(dchg, feba, 8);
+
+    unsafe { _mm_storeu_si128(state.as_mut_ptr() as *mut __m128i, dcba) };
+    unsafe { _mm_storeu_si128(state.as_mut_ptr().add(4) as *mut __m128i, hgfe) };
+}
+
+macro_rules! rotate_right {
+    ($x:expr, $n:literal) => {
+        _mm_or_si128(_mm_srli_epi32::<$n>($x), _mm_slli_epi32::<{ 32 - $n }>($x))
+    };
+}
+
+#[inline]
+#[target_feature(enable = "sse2")]
+fn sigma0(x: __m128i) -> __m128i {
+    let r = _mm_xor_si128(rotate_right!(x, 7), rotate_right!(x, 18));
+    _mm_xor_si128(r, _mm_srli_epi32::<3>(x))
+}
+
+#[inline]
+#[target_feature(enable = "sse2")]
+fn sigma1(x: __m128i) -> __m128i {
+    let r = _mm_xor_si128(rotate_right!(x, 17), rotate_right!(x, 19));
+    _mm_xor_si128(r, _mm_srli_epi32::<10>(x))
+}
+
+// Computes the message schedule four words at a time and leaves the rounds,
+// which are inherently serial, to the portable implementation. Since w[t] for
+// the upper two lanes depends on w[t - 2] of the lower two lanes, sigma1 is
+// applied in two halves.
+#[target_feature(enable = "sse2,ssse3")]
+pub fn update_state_ssse3(state: &mut [u32; 8], bytes: &[u8; 64]) {
+    let mask = _mm_set_epi64x(BYTE_SWAP.0, BYTE_SWAP.1);
+    let mut v = [_mm_setzero_si128(); 16];
+
+    for (i, v) in v.iter_mut().take(4).enumerate() {
+        let block = unsafe { _mm_loadu_si128(bytes.as_ptr().add(16 * i) as *const __m128i) };
+        *v = _mm_shuffle_epi8(block, mask);
+    }
+
+    for i in 4..16 {
+        // w[t - 15..t - 11] and w[t - 7..t - 3]
+        let w15 = _mm_alignr_epi8(v[i - 3], v[i - 4], 4);
+        let w7 = _mm_alignr_epi8(v[i - 1], v[i - 2], 4);
+        let x = _mm_add_epi32(_mm_add_epi32(v[i - 4], sigma0(w15)), w7);
+
+        // w[t - 2] and w[t - 1] move to the lower half
+        let w2 = _mm_shuffle_epi32(v[i - 1], 0xFE);
+        let x = _mm_add_epi32(x, _mm_move_epi64(sigma1(w2)));
+
+        // the lower half of the result feeds the upper half
+        v[i] = _mm_add_epi32(x, _mm_slli_si128::<8>(sigma1(x)));
+    }
+
+    let mut w = [0u32; 64];
+
+    for (i, v) in v.iter().enumerate() {
+        unsafe { _mm_storeu_si128(w.as_mut_ptr().add(4 * i) as *mut __m128i, *v) };
+    }
+
+    hash::rounds(state, &w);
+}
+
+macro_rules! rotate_right_256 {
+    ($x:expr, $n:literal) => {
+        _mm256_or_si256(
+            _mm256_srli_epi32::<$n>($x),
+            _mm256_slli_epi32::<{ 32 - $n }>($x),
+        )
+    };
+}
+
+// Compresses one block for each of eight independent states, with lane j of
+// every register holding the words of states[j] and blocks[j].
+#[target_feature(enable = "avx2")]
+pub fn update_states_avx2(states: &mut [[u32; 8]; 8], blocks: [&[u8; 64]; 8]) {
+    let mut w = [_mm256_setzero_si256(); 64];
+
+    for (t, w) in w.iter_mut().take(16).enumerate() {
+        let word: [i32; 8] = core::array::from_fn(|j| {
+            u32::from_be_bytes(blocks[j][4 * t..4 * t + 4].try_into().unwrap()) as i32
+        });
+
+        *w = _mm256_setr_epi32(
+            word[0], word[1], word[2], word[3], word[4], word[5], word[6], word[7],
+        );
+    }
+
+    for t in 16..64 {
+        let s0 = _mm256_xor_si256(
+            _mm256_xor_si256(
+                rotate_right_256!(w[t - 15], 7),
+                rotate_right_256!(w[t - 15], 18),
+            ),
+            _mm256_srli_epi32::<3>(w[t - 15]),
+        );
+        let s1 = _mm256_xor_si256(
+            _mm256_xor_si256(
+                rotate_right_256!(w[t - 2], 17),
+                rotate_right_256!(w[t - 2], 19),
+            ),
+            _mm256_srli_epi32::<10>(w[t - 2]),
+        );
+
+        w[t] = _mm256_add_epi32(
+            _mm256_add_epi32(w[t - 16], s0),
+            _mm256_add_epi32(w[t - 7], s1),
+        );
+    }
+
+    let mut h = [_mm256_setzero_si256(); 8];
+
+    for (k, h) in h.iter_mut().enumerate() {
+        let word: [i32; 8] = core::array::from_fn(|j| states[j][k] as i32);
+
+        *h = _mm256_setr_epi32(
+            word[0], word[1], word[2], word[3], word[4], word[5], word[6], word[7],
+        );
+    }
+
+    let start = h;
+
+    for (t, w) in w.iter().enumerate() {
+        let ch = _mm256_xor_si256(
+            _mm256_and_si256(h[4], h[5]),
+            _mm256_andnot_si256(h[4], h[6]),
+        );
+        let ma = _mm256_xor_si256(
+            _mm256_xor_si256(_mm256_and_si256(h[0], h[1]), _mm256_and_si256(h[0], h[2])),
+            _mm256_and_si256(h[1], h[2]),
+        );
+        let s0 = _mm256_xor_si256(
+            _mm256_xor_si256(rotate_right_256!(h[0], 2), rotate_right_256!(h[0], 13)),
+            rotate_right_256!(h[0], 22),
+        );
+        let s1 = _mm256_xor_si256(
+            _mm256_xor_si256(rotate_right_256!(h[4], 6), rotate_right_256!(h[4], 11)),
+            rotate_right_256!(h[4], 25),
+        );
+        let t0 = _mm256_add_epi32(
+            _mm256_add_epi32(h[7], s1),
+            _mm256_add_epi32(
+                ch,
+                _mm256_add_epi32(_mm256_set1_epi32(hash::K[t] as i32), *w),
+            ),
+        );
+        let t1 = _mm256_add_epi32(s0, ma);
+
+        h[7] = h[6];
+        h[6] = h[5];
+        h[5] = h[4];
+        h[4] = _mm256_add_epi32(h[3], t0);
+        h[3] = h[2];
+        h[2] = h[1];
+        h[1] = h[0];
+        h[0] = _mm256_add_epi32(t0, t1);
+    }
+
+    for k in 0..8 {
+        let mut words = [0u32; 8];
+        let sum = _mm256_add_epi32(h[k], start[k]);
+        unsafe { _mm256_storeu_si256(words.as_mut_ptr() as *mut __m256i, sum) };
+
+        for j in 0..8 {
+            states[j][k] = words[j];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hash;
+
+    // Pseudorandom states and blocks from a hash chain.
+    fn random_inputs(n: usize) -> Vec<([u32; 8], [u8; 64])> {
+        let mut seed = [0u8; 32];
+
+        (0..n)
+            .map(|_| {
+                let mut bytes = [0u8; 96];
+
+                for chunk in bytes.chunks_mut(32) {
+                    let mut hasher = hash::Sha256::empty();
+                    hasher.write(&seed);
+                    seed = hasher.finish();
+                    chunk.copy_from_slice(&seed);
+                }
+
+                let state = core::array::from_fn(|i| {
+                    u32::from_le_bytes(bytes[4 * i..4 * i + 4].try_into().unwrap())
+                });
+
+                (state, bytes[32..].try_into().unwrap())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sha() {
+        if !is_x86_feature_detected!("sha") || !is_x86_feature_detected!("sse4.1") {
+            return;
+        }
+
+        for (state, block) in random_inputs(1000) {
+            let mut expected = state;
+            hash::update_state_portable(&mut expected, &block);
+
+            let mut state = state;
+            unsafe { super::update_state_sha(&mut state, &block) };
+
+            assert!(state == expected);
+        }
+    }
+
+    #[test]
+    fn ssse3() {
+        if !is_x86_feature_detected!("ssse3") {
+            return;
+        }
+
+        for (state, block) in random_inputs(1000) {
+            let mut expected = state;
+            hash::update_state_portable(&mut expected, &block);
+
+            let mut state = state;
+            unsafe { super::update_state_ssse3(&mut state, &block) };
+
+            assert!(state == expected);
+        }
+    }
+
+    #[test]
+    fn avx2() {
+        if !is_x86_feature_detected!("avx2") {
+            return;
+        }
+
+        let inputs = random_inputs(800);
+
+        for chunk in inputs.chunks(8) {
+            let mut states: [[u32; 8]; 8] = core::array::from_fn(|j| chunk[j].0);
+            let blocks = core::array::from_fn(|j| &chunk[j].1);
+            unsafe { super::update_states_avx2(&mut states, blocks) };
+
+            for (j, (state, block)) in chunk.iter().enumerate() {
+                let mut expected = *state;
+                hash::update_state_portable(&mut expected, block);
+
+                assert!(states[j] == expected);
+            }
+        }
+    }
+}
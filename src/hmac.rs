@@ -0,0 +1,256 @@
+use super::hash::HashFunction;
+
+// HMAC as specified in RFC2104, where the inner hash function is already keyed
+// so that the message can be written in pieces.
+#[derive(Clone)]
+pub struct Hmac<H: HashFunction> {
+    inner: H,
+    outer: H,
+}
+
+impl<H: HashFunction> Hmac<H> {
+    pub fn new(key: &[u8]) -> Self {
+        let mut padded_key = vec![0; H::BLOCK_SIZE];
+
+        // keys longer than a block are replaced by their hash
+        if key.len() > H::BLOCK_SIZE {
+            let mut hasher = H::empty();
+            hasher.write(key);
+            let digest = hasher.finish();
+            padded_key[..H::OUTPUT_SIZE].copy_from_slice(digest.as_ref());
+        } else {
+            padded_key[..key.len()].copy_from_slice(key);
+        }
+
+        let mut inner = H::empty();
+        let mut outer = H::empty();
+
+        inner.write(&padded_key.iter().map(|b| b ^ 0x36).collect::<Vec<u8>>());
+        outer.write(&padded_key.iter().map(|b| b ^ 0x5C).collect::<Vec<u8>>());
+
+        Self { inner, outer }
+    }
+
+    pub fn write(&mut self, bytes: &[u8]) {
+        self.inner.write(bytes);
+    }
+
+    pub fn finish(mut self) -> H::Digest {
+        self.outer.write(self.inner.finish().as_ref());
+        self.outer.finish()
+    }
+}
+
+// The extract step of HKDF from RFC5869. An empty salt is equivalent to the
+// default salt of OUTPUT_SIZE zero bytes, as HMAC pads the key with zeros.
+pub fn hkdf_extract<H: HashFunction>(salt: &[u8], input_key: &[u8]) -> H::Digest {
+    let mut hmac = Hmac::<H>::new(salt);
+    hmac.write(input_key);
+    hmac.finish()
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum HkdfError {
+    OutputTooLong,
+}
+
+// The expand step of HKDF from RFC5869, which fills the output with
+// T(1) | T(2) | ... where T(i) = HMAC(prk, T(i - 1) | info | i). The output
+// can be at most 255 blocks long, since i is a single byte.
+pub fn hkdf_expand<H: HashFunction>(
+    pseudorandom_key: &[u8],
+    info: &[u8],
+    output: &mut [u8],
+) -> Result<(), HkdfError> {
+    if output.len() > 255 * H::OUTPUT_SIZE {
+        return Err(HkdfError::OutputTooLong);
+    }
+
+    // the keyed state is computed once and cloned for every block
+    let keyed = Hmac::<H>::new(pseudorandom_key);
+    let mut previous: Option<H::Digest> = None;
+
+    for (i, chunk) in output.chunks_mut(H::OUTPUT_SIZE).enumerate() {
+        let mut hmac = keyed.clone();
+
+        if let Some(previous) = &previous {
+            hmac.write(previous.as_ref());
+        }
+
+        hmac.write(info);
+        hmac.write(&[i as u8 + 1]);
+
+        let t = hmac.finish();
+        chunk.copy_from_slice(&t.as_ref()[..chunk.len()]);
+        previous = Some(t);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hash::Sha256;
+
+    fn hmac(key: &[u8], data: &[u8]) -> [u8; 32] {
+        let mut hmac = super::Hmac::<Sha256>::new(key);
+        hmac.write(data);
+        hmac.finish()
+    }
+
+    // Test cases 1 to 4, 6 and 7 from RFC4231, case 5 truncates the output
+    #[test]
+    fn rfc4231() {
+        let test_cases: [(&[u8], &[u8], [u8; 32]); 6] = [
+            (
+                &[0x0B; 20],
+                b"Hi There",
+                hex_literal::hex!(
+                    "B0344C61D8DB38535CA8AFCEAF0BF12B"
+                    "881DC200C9833DA726E9376C2E32CFF7"
+                ),
+            ),
+            (
+                b"Jefe",
+                b"what do ya want for nothing?",
+                hex_literal::hex!(
+                    "5BDCC146BF60754E6A042426089575C7"
+                    "5A003F089D2739839DEC58B964EC3843"
+                ),
+            ),
+            (
+                &[0xAA; 20],
+                &[0xDD; 50],
+                hex_literal::hex!(
+                    "773EA91E36800E46854DB8EBD09181A7"
+                    "2959098B3EF8C122D9635514CED565FE"
+                ),
+            ),
+            (
+                &hex_literal::hex!("0102030405060708090A0B0C0D0E0F10111213141516171819"),
+                &[0xCD; 50],
+                hex_literal::hex!(
+                    "82558A389A443C0EA4CC819899F2083A"
+                    "85F0FAA3E578F8077A2E3FF46729665B"
+                ),
+            ),
+            (
+                &[0xAA; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First",
+                hex_literal::hex!(
+                    "60E431591EE0B67F0D8A26AACBF5B77F"
+                    "8E0BC6213728C5140546040F0EE37F54"
+                ),
+            ),
+            (
+                &[0xAA; 131],
+                b"This is a test using a larger than block-size key and a larger than \
+                  block-size data. The key needs to be hashed before being used by the \
+                  HMAC algorithm.",
+                hex_literal::hex!(
+                    "9B09FFA71B942FCB27635FBCD5B0E944"
+                    "BFDC63644F0713938A7F51535C3A35E2"
+                ),
+            ),
+        ];
+
+        for (key, data, expected) in test_cases {
+            assert!(hmac(key, data) == expected);
+        }
+    }
+
+    #[test]
+    fn streaming() {
+        let data = [0x5A; 200];
+        let expected = hmac(b"key", &data);
+
+        for split in [0, 1, 63, 64, 65, 200] {
+            let mut hmac = super::Hmac::<Sha256>::new(b"key");
+            hmac.write(&data[..split]);
+            hmac.write(&data[split..]);
+
+            assert!(hmac.finish() == expected);
+        }
+    }
+
+    // Test cases 1 to 3 from RFC5869
+    #[test]
+    fn rfc5869() {
+        let salt: Vec<u8> = (0x00..=0x0C).collect();
+        let info: Vec<u8> = (0xF0..=0xF9).collect();
+        let prk = super::hkdf_extract::<Sha256>(&salt, &[0x0B; 22]);
+
+        assert!(
+            prk == hex_literal::hex!(
+                "077709362C2E32DF0DDC3F0DC47BBA63"
+                "90B6C73BB50F9C3122EC844AD7C2B3E5"
+            )
+        );
+
+        let mut okm = [0; 42];
+        super::hkdf_expand::<Sha256>(&prk, &info, &mut okm).unwrap();
+
+        assert!(
+            okm == hex_literal::hex!(
+                "3CB25F25FAACD57A90434F64D0362F2A"
+                "2D2D0A90CF1A5A4C5DB02D56ECC4C5BF"
+                "34007208D5B887185865"
+            )
+        );
+
+        let input_key: Vec<u8> = (0x00..=0x4F).collect();
+        let salt: Vec<u8> = (0x60..=0xAF).collect();
+        let info: Vec<u8> = (0xB0..=0xFF).collect();
+        let prk = super::hkdf_extract::<Sha256>(&salt, &input_key);
+
+        assert!(
+            prk == hex_literal::hex!(
+                "06A6B88C5853361A06104C9CEB35B45C"
+                "EF760014904671014A193F40C15FC244"
+            )
+        );
+
+        let mut okm = [0; 82];
+        super::hkdf_expand::<Sha256>(&prk, &info, &mut okm).unwrap();
+
+        assert!(
+            okm == hex_literal::hex!(
+                "B11E398DC80327A1C8E7F78C596A4934"
+                "4F012EDA2D4EFAD8A050CC4C19AFA97C"
+                "59045A99CAC7827271CB41C65E590E09"
+                "DA3275600C2F09B8367793A9ACA3DB71"
+                "CC30C58179EC3E87C14C01D5C1F3434F"
+                "1D87"
+            )
+        );
+
+        let prk = super::hkdf_extract::<Sha256>(&[], &[0x0B; 22]);
+
+        assert!(
+            prk == hex_literal::hex!(
+                "19EF24A32C717B167F33A91D6F648BDF"
+                "96596776AFDB6377AC434C1C293CCB04"
+            )
+        );
+
+        let mut okm = [0; 42];
+        super::hkdf_expand::<Sha256>(&prk, &[], &mut okm).unwrap();
+
+        assert!(
+            okm == hex_literal::hex!(
+                "8DA4E775A563C18F715F802A063C5A31"
+                "B8A11F5C5EE1879EC3454E5F3C738D2D"
+                "9D201395FAA4B61A96C8"
+            )
+        );
+
+        let mut okm = vec![0; 255 * 32];
+        assert!(super::hkdf_expand::<Sha256>(&prk, &[], &mut okm).is_ok());
+
+        let mut okm = vec![0; 255 * 32 + 1];
+        assert!(
+            super::hkdf_expand::<Sha256>(&prk, &[], &mut okm)
+                == Err(super::HkdfError::OutputTooLong)
+        );
+    }
+}
@@ -1,7 +1,23 @@
-#![allow(dead_code)]
+mod ecdsa;
 mod field;
 mod field_inversion;
 mod field_multiplication;
 mod group;
+mod group_multiplication;
 mod hash;
+#[cfg(target_arch = "x86_64")]
+mod hash_x86_64;
+mod hmac;
+mod keys;
+mod scalar;
 mod schnorr;
+
+pub use ecdsa::EcdsaError;
+pub use group_multiplication::WNAF_WIDTH;
+pub use hash::{HashFunction, Midstate, Sha256, TaggedHash};
+pub use hmac::{hkdf_expand, hkdf_extract, HkdfError, Hmac};
+pub use keys::{
+    verify_batch, EcdsaSignature, Keypair, ParseError, PublicKey, RecoverableSignature, SecretKey,
+    Signature, XOnlyPublicKey,
+};
+pub use schnorr::VerificationError;
@@ -0,0 +1,341 @@
+use core::convert::From;
+use core::fmt;
+use core::ops::{Add, Mul, Neg, Sub};
+
+const ORDER: [u64; 4] = [
+    0xBFD25E8CD0364141,
+    0xBAAEDCE6AF48A03B,
+    0xFFFFFFFFFFFFFFFE,
+    0xFFFFFFFFFFFFFFFF,
+];
+
+// (n - 1) / 2
+const HALF_ORDER: [u64; 4] = [
+    0xDFE92F46681B20A0,
+    0x5D576E7357A4501D,
+    0xFFFFFFFFFFFFFFFF,
+    0x7FFFFFFFFFFFFFFF,
+];
+
+// 2^256 - n
+const ORDER_COMPLEMENT: [u64; 3] = [0x402DA1732FC9BEBF, 0x4551231950B75FC4, 0x1];
+
+// The limbs are stored in little endian order and the represented value is
+// always fully reduced modulo the group order n.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Scalar {
+    limbs: [u64; 4],
+}
+
+// Scalars are frequently secret keys or nonces, so their value is not printed.
+impl fmt::Debug for Scalar {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Scalar(..)")
+    }
+}
+
+fn add_limbs(a: [u64; 4], b: [u64; 4]) -> ([u64; 4], u64) {
+    let mut sum = [0; 4];
+    let mut carry = 0;
+
+    for i in 0..4 {
+        let t = a[i] as u128 + b[i] as u128 + carry;
+        sum[i] = t as u64;
+        carry = t >> 64;
+    }
+
+    (sum, carry as u64)
+}
+
+fn subtract_limbs(a: [u64; 4], b: [u64; 4]) -> ([u64; 4], u64) {
+    let mut difference = [0; 4];
+    let mut borrow = 0;
+
+    for i in 0..4 {
+        let (t, b1) = a[i].overflowing_sub(b[i]);
+        let (t, b2) = t.overflowing_sub(borrow);
+        difference[i] = t;
+        borrow = (b1 | b2) as u64;
+    }
+
+    (difference, borrow)
+}
+
+// Returns a if the mask is all ones and b if it is zero.
+fn select_limbs(a: [u64; 4], b: [u64; 4], mask: u64) -> [u64; 4] {
+    core::array::from_fn(|i| (a[i] & mask) | (b[i] & !mask))
+}
+
+fn multiply_limbs(a: [u64; 4], b: [u64; 4]) -> [u64; 8] {
+    let mut product = [0; 8];
+
+    for i in 0..4 {
+        let mut carry = 0;
+
+        for j in 0..4 {
+            let t = product[i + j] as u128 + a[i] as u128 * b[j] as u128 + carry;
+            product[i + j] = t as u64;
+            carry = t >> 64;
+        }
+
+        product[i + 4] = carry as u64;
+    }
+
+    product
+}
+
+impl Scalar {
+    pub const ZERO: Scalar = Scalar { limbs: [0; 4] };
+
+    pub const ONE: Scalar = Scalar {
+        limbs: [1, 0, 0, 0],
+    };
+
+    fn verify(self) {
+        assert!(subtract_limbs(self.limbs, ORDER).1 == 1);
+    }
+
+    // Reduces a value smaller than 2n, given by four limbs and a carry bit
+    // representing 2^256, by subtracting n at most once.
+    fn normalize(limbs: [u64; 4], carry: u64) -> Self {
+        assert!(carry >> 1 == 0);
+
+        let (difference, borrow) = subtract_limbs(limbs, ORDER);
+
+        // If the value is larger than 2^256 the subtraction has to borrow
+        // from the carry bit, otherwise we subtract only if it did not borrow.
+        // The difference is selected with a mask to not branch on the value.
+        let mask = (carry | (borrow ^ 1)).wrapping_neg();
+        let limbs = select_limbs(difference, limbs, mask);
+
+        let scalar = Self { limbs };
+        scalar.verify();
+        scalar
+    }
+
+    // Reduces a 512 bit value modulo n by repeatedly replacing the upper half
+    // h * 2^256 with h * (2^256 - n), which is congruent modulo n.
+    fn reduce(mut limbs: [u64; 8]) -> Self {
+        // Since 2^256 - n < 2^129 the bound of the value decreases as follows:
+        // 2^512 -> 2^386 -> 2^260 -> 2^256 + 2^133 -> 2^256. In the last step
+        // the upper half can only be one if the lower half is less than 2^133.
+        for _ in 0..4 {
+            let mut folded = [0; 8];
+            folded[..4].copy_from_slice(&limbs[..4]);
+
+            for i in 0..4 {
+                let mut carry = 0;
+
+                for j in 0..3 {
+                    let t = folded[i + j] as u128
+                        + limbs[4 + i] as u128 * ORDER_COMPLEMENT[j] as u128
+                        + carry;
+                    folded[i + j] = t as u64;
+                    carry = t >> 64;
+                }
+
+                for l in folded[i + 3..].iter_mut() {
+                    let t = *l as u128 + carry;
+                    *l = t as u64;
+                    carry = t >> 64;
+                }
+
+                assert!(carry == 0);
+            }
+
+            limbs = folded;
+        }
+
+        assert!(limbs[4..] == [0; 4]);
+
+        Self::normalize(limbs[..4].try_into().unwrap(), 0)
+    }
+
+    pub fn decode(bytes: [u8; 32]) -> Self {
+        let b3 = u64::from_be_bytes(bytes[..8].try_into().unwrap());
+        let b2 = u64::from_be_bytes(bytes[8..16].try_into().unwrap());
+        let b1 = u64::from_be_bytes(bytes[16..24].try_into().unwrap());
+        let b0 = u64::from_be_bytes(bytes[24..].try_into().unwrap());
+
+        Self::normalize([b0, b1, b2, b3], 0)
+    }
+
+    pub fn encode(self) -> [u8; 32] {
+        let [b0, b1, b2, b3] = self.limbs;
+
+        [b3, b2, b1, b0]
+            .map(u64::to_be_bytes)
+            .concat()
+            .try_into()
+            .unwrap()
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.limbs == [0, 0, 0, 0]
+    }
+
+    // Returns true if the scalar is larger than (n - 1) / 2.
+    pub fn is_high(self) -> bool {
+        subtract_limbs(HALF_ORDER, self.limbs).1 == 1
+    }
+
+    // Computes round(a * b / 2^384) without reducing the product modulo n.
+    pub(crate) fn multiply_shift_384(self, rhs: Self) -> Self {
+        let product = multiply_limbs(self.limbs, rhs.limbs);
+
+        // bit 383 of the product decides whether we round up
+        let (limbs, carry) = add_limbs([product[6], product[7], 0, 0], [product[5] >> 63, 0, 0, 0]);
+        assert!(carry == 0);
+
+        Self { limbs }
+    }
+
+    pub fn negative(self) -> Self {
+        self.cond_negate(true)
+    }
+
+    // Negates the scalar if the flag is set, without branching on the flag or
+    // the value of the scalar.
+    pub fn cond_negate(self, flag: bool) -> Self {
+        let nonzero = self.limbs.iter().fold(0, |acc, l| acc | l);
+        let nonzero = (nonzero | nonzero.wrapping_neg()) >> 63;
+
+        // n - 0 = n is not reduced, so zero is never negated
+        let mask = (flag as u64 & nonzero).wrapping_neg();
+
+        Self {
+            limbs: select_limbs(subtract_limbs(ORDER, self.limbs).0, self.limbs, mask),
+        }
+    }
+
+    pub fn square(self) -> Self {
+        self * self
+    }
+
+    // Returns None for zero, which has no inverse.
+    pub fn inverse(self) -> Option<Self> {
+        // n - 2
+        const EXPONENT: [u8; 32] = hex_literal::hex!(
+            "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFE"
+            "BAAEDCE6AF48A03BBFD25E8CD036413F"
+        );
+
+        if self.is_zero() {
+            return None;
+        }
+
+        let mut x = Self::ONE;
+
+        EXPONENT
+            .into_iter()
+            .flat_map(|x| (0..8).rev().map(move |s| x & (1 << s) != 0))
+            .for_each(|b| {
+                x = if b { x.square() * self } else { x.square() };
+            });
+
+        Some(x)
+    }
+}
+
+impl Add for Scalar {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        let (sum, carry) = add_limbs(self.limbs, rhs.limbs);
+        Self::normalize(sum, carry)
+    }
+}
+
+impl Sub for Scalar {
+    type Output = Self;
+
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn sub(self, rhs: Self) -> Self {
+        self + rhs.negative()
+    }
+}
+
+impl Mul for Scalar {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self::reduce(multiply_limbs(self.limbs, rhs.limbs))
+    }
+}
+
+impl Neg for Scalar {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        self.negative()
+    }
+}
+
+impl From<u64> for Scalar {
+    fn from(n: u64) -> Self {
+        Self {
+            limbs: [n, 0, 0, 0],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn decode() {
+        let order_plus_one: [u8; 32] = hex_literal::hex!(
+        "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFE"
+        "BAAEDCE6AF48A03BBFD25E8CD0364142"
+        );
+
+        assert!(super::Scalar::decode(order_plus_one) == super::Scalar::ONE);
+        assert!(super::Scalar::decode([0; 32]) == super::Scalar::ZERO);
+        assert!(super::Scalar::decode(super::Scalar::ONE.encode()) == super::Scalar::ONE);
+    }
+
+    #[test]
+    fn arithmetic() {
+        let minus_one = super::Scalar::ONE.negative();
+
+        assert!(minus_one + super::Scalar::ONE == super::Scalar::ZERO);
+        assert!(-minus_one == super::Scalar::ONE);
+        assert!(minus_one * minus_one == super::Scalar::ONE);
+        assert!(super::Scalar::ZERO - super::Scalar::ONE == minus_one);
+        assert!(super::Scalar::from(6) * super::Scalar::from(7) == 42.into());
+    }
+
+    #[test]
+    fn cond_negate() {
+        let x = super::Scalar::from(5);
+
+        assert!(x.cond_negate(true) == -x);
+        assert!(x.cond_negate(false) == x);
+        assert!(super::Scalar::ZERO.cond_negate(true) == super::Scalar::ZERO);
+        assert!(super::Scalar::ZERO.cond_negate(false) == super::Scalar::ZERO);
+        assert!((-x).cond_negate(true) == x);
+    }
+
+    #[test]
+    fn is_high() {
+        let minus_one = super::Scalar::ONE.negative();
+        let half = super::Scalar::from(2).inverse().unwrap() * minus_one;
+
+        assert!(!super::Scalar::ONE.is_high());
+        assert!(!half.is_high());
+        assert!((half + super::Scalar::ONE).is_high());
+        assert!(minus_one.is_high());
+    }
+
+    #[test]
+    fn inverse() {
+        let mut x = super::Scalar::decode(hex_literal::hex!(
+        "B7E151628AED2A6ABF7158809CF4F3C7"
+        "62E7160F38B4DA56A784D9045190CFEF"
+        ));
+
+        for _ in 0..32 {
+            assert!(x * x.inverse().unwrap() == super::Scalar::ONE);
+            x = x.square() + x;
+        }
+    }
+}
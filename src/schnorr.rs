@@ -1,31 +1,54 @@
 use super::field;
 use super::group;
+use super::group_multiplication;
 use super::hash;
+use super::scalar;
 
-const PRIME: [u8; 32] = hex_literal::hex!(
+use std::sync::OnceLock;
+
+pub const PRIME: [u8; 32] = hex_literal::hex!(
     "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF"
     "FFFFFFFFFFFFFFFFFFFFFFFEFFFFFC2F"
 );
-const GROUP_ORDER: [u8; 32] = hex_literal::hex!(
+pub const GROUP_ORDER: [u8; 32] = hex_literal::hex!(
     "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFE"
     "BAAEDCE6AF48A03BBFD25E8CD0364141"
 );
 
-fn hash(a: &[u8; 32], b: &[u8; 32], c: &[u8; 32]) -> [u8; 32] {
-    let tag_hash = hex_literal::hex!(
-        "7BB52D7A9FEF58323EB1BF7A407DB382"
-        "D2F3F2D81BB1224F49FE518F6D48D37C"
-    );
+static CHALLENGE: OnceLock<hash::TaggedHash> = OnceLock::new();
+static AUX: OnceLock<hash::TaggedHash> = OnceLock::new();
+static NONCE: OnceLock<hash::TaggedHash> = OnceLock::new();
+
+fn hash(a: &[u8; 32], b: &[u8; 32], c: &[u8]) -> [u8; 32] {
+    let mut hasher = CHALLENGE
+        .get_or_init(|| hash::TaggedHash::new("BIP0340/challenge"))
+        .hasher();
 
-    let mut hasher = hash::Sha256::empty();
-    hasher.write(&tag_hash);
-    hasher.write(&tag_hash);
     hasher.write(a);
     hasher.write(b);
     hasher.write(c);
     hasher.finish()
 }
 
+fn hash_aux(a: &[u8; 32]) -> [u8; 32] {
+    AUX.get_or_init(|| hash::TaggedHash::new("BIP0340/aux"))
+        .hash(a)
+}
+
+fn hash_nonce(a: &[u8; 32], b: &[u8; 32], c: &[u8]) -> [u8; 32] {
+    let mut hasher = NONCE
+        .get_or_init(|| hash::TaggedHash::new("BIP0340/nonce"))
+        .hasher();
+
+    hasher.write(a);
+    hasher.write(b);
+    hasher.write(c);
+    hasher.finish()
+}
+
+// Simple double-and-add, only used as a reference for the optimized
+// multiplication routines in group_multiplication.
+#[cfg(test)]
 pub fn multiply_by_scalar(point: group::Point, scalar: [u8; 32]) -> group::Point {
     let mut g = group::Point::E;
 
@@ -42,14 +65,44 @@ pub fn multiply_by_scalar(point: group::Point, scalar: [u8; 32]) -> group::Point
 
 pub fn public_key(secret_key: [u8; 32]) -> [u8; 32] {
     assert!(secret_key != [0; 32]);
-    assert!(secret_key < PRIME);
+    assert!(secret_key < GROUP_ORDER);
 
-    multiply_by_scalar(group::GENERATOR, secret_key)
-        .affine_x()
-        .encode()
+    let (x, _) = group_multiplication::multiply_generator(secret_key).to_affine();
+    x.encode()
 }
 
-#[derive(Debug)]
+pub fn sign(secret_key: [u8; 32], message: &[u8], aux_rand: [u8; 32]) -> ([u8; 32], [u8; 32]) {
+    assert!(secret_key != [0; 32]);
+    assert!(secret_key < GROUP_ORDER);
+
+    let (x, y) = group_multiplication::multiply_generator(secret_key).to_affine();
+    let public_key = x.encode();
+
+    // negated without branches, since d and the parity of its y coordinate
+    // are secret
+    let d = scalar::Scalar::decode(secret_key).cond_negate(!y.is_even());
+
+    let mut t = hash_aux(&aux_rand);
+
+    for (t, d) in t.iter_mut().zip(d.encode()) {
+        *t ^= d;
+    }
+
+    let k = scalar::Scalar::decode(hash_nonce(&t, &public_key, message));
+
+    assert!(!k.is_zero());
+
+    let (x, y) = group_multiplication::multiply_generator(k.encode()).to_affine();
+    let r = x.encode();
+
+    let k = k.cond_negate(!y.is_even());
+
+    let e = scalar::Scalar::decode(hash(&r, &public_key, message));
+
+    (r, (k + e * d).encode())
+}
+
+#[derive(Debug, PartialEq, Eq)]
 pub enum VerificationError {
     PublicKeyOutOfBounds,
     SignatureOutOfBounds,
@@ -60,37 +113,39 @@ pub enum VerificationError {
 }
 
 pub fn solve_for_even_y(x: field::Element) -> Result<field::Element, VerificationError> {
-    const MAGIC: [u8; 32] = hex_literal::hex!(
-        "3FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF"
-        "FFFFFFFFFFFFFFFFFFFFFFFFBFFFFF0C"
-    );
+    let point = group::AffinePoint::lift_x(x, false).ok_or(VerificationError::FailedToSolve)?;
 
-    let c = x.square() * x + 7.into();
-
-    let mut y = field::ONE;
-
-    MAGIC
-        .into_iter()
-        .flat_map(|x| (0..8).rev().map(move |s| x & (1 << s) != 0))
-        .for_each(|b| {
-            y = if b { y.square() * c } else { y.square() };
-        });
-
-    if y.square() != c {
-        return Err(VerificationError::FailedToSolve);
-    }
+    Ok(point.y)
+}
 
-    if !y.is_even() {
-        y = y.negative();
-    }
+fn lift_x(x: [u8; 32]) -> Result<group::AffinePoint, VerificationError> {
+    let x = field::Element::decode(x);
+    let y = solve_for_even_y(x)?;
 
-    Ok(y)
+    Ok(group::AffinePoint { x, y })
 }
 
 pub fn verify_signature(
     public_key: [u8; 32],
-    message: [u8; 32],
+    message: &[u8],
+    signature: ([u8; 32], [u8; 32]),
+) -> Result<(), VerificationError> {
+    verify_signature_with_width(
+        public_key,
+        message,
+        signature,
+        group_multiplication::WNAF_WIDTH,
+    )
+}
+
+// Verifies a signature with a wNAF recoding of the given width for the
+// multiplication, which trades the number of additions for the size of the
+// tables of odd multiples.
+pub fn verify_signature_with_width(
+    public_key: [u8; 32],
+    message: &[u8],
     (r, s): ([u8; 32], [u8; 32]),
+    width: usize,
 ) -> Result<(), VerificationError> {
     if public_key >= PRIME {
         return Err(VerificationError::PublicKeyOutOfBounds);
@@ -100,29 +155,112 @@ pub fn verify_signature(
         return Err(VerificationError::SignatureOutOfBounds);
     };
 
-    let public_x = field::Element::decode(public_key);
-    let public_y = solve_for_even_y(public_x)?;
-    let public_point = group::Point::P(public_x, public_y, field::ONE);
+    let public_point = lift_x(public_key)?;
 
-    let e = hash(&r, &public_key, &message);
+    let e = scalar::Scalar::decode(hash(&r, &public_key, message));
 
-    let g = multiply_by_scalar(group::GENERATOR, s);
-    let p = multiply_by_scalar(public_point, e);
-    let h = g.add(p.negative());
+    let h = group_multiplication::multiply_multi_endomorphism(
+        &[
+            (group::AffinePoint::GENERATOR, s),
+            (public_point.negative(), e.encode()),
+        ],
+        width,
+    );
 
-    if h.is_neutral() {
+    check_nonce_point(h, r)
+}
+
+// Checks that R = s * G - e * P has the x coordinate r and an even y
+// coordinate. The affine x coordinate x / z^2 equals r if and only if
+// x = r * z^2, so invalid signatures are mostly rejected without an inversion.
+// The parity of y / z^3 is not determined by y and z alone, so the one
+// inversion remains for valid signatures.
+pub fn check_nonce_point(h: group::Point, r: [u8; 32]) -> Result<(), VerificationError> {
+    let group::Point::P(x, y, z) = h else {
         return Err(VerificationError::IsNeutral);
+    };
+
+    let z2 = z.square();
+
+    if x != field::Element::decode(r) * z2 {
+        return Err(VerificationError::NotEqual);
     }
 
-    if !h.affine_y().is_even() {
+    if !(y * (z2 * z).inverse()).is_even() {
         return Err(VerificationError::IsOdd);
     }
 
-    if h.affine_x().encode() != r {
-        return Err(VerificationError::NotEqual);
+    Ok(())
+}
+
+pub type BatchEntry<'a> = ([u8; 32], &'a [u8], ([u8; 32], [u8; 32]));
+
+pub fn verify_batch(batch: &[BatchEntry]) -> Result<(), (usize, VerificationError)> {
+    if verify_batch_equation(batch) {
+        return Ok(());
     }
 
-    Ok(())
+    // At least one signature is invalid, so we check them one by one to find it.
+    for (i, (public_key, message, signature)) in batch.iter().enumerate() {
+        verify_signature(*public_key, message, *signature).map_err(|e| (i, e))?;
+    }
+
+    unreachable!("the batch equation holds if all signatures are valid");
+}
+
+fn verify_batch_equation(batch: &[BatchEntry]) -> bool {
+    // The coefficients are derived from a hash of the entire batch such that
+    // they can not be predicted by whoever created the signatures.
+    let mut hasher = hash::Sha256::empty();
+
+    for (public_key, message, (r, s)) in batch {
+        hasher.write(public_key);
+        hasher.write(&(message.len() as u64).to_be_bytes());
+        hasher.write(message);
+        hasher.write(r);
+        hasher.write(s);
+    }
+
+    let seed = hasher.finish();
+
+    // We check that (a_1 * s_1 + ... + a_u * s_u) * G - a_1 * R_1 - ... - a_u * R_u
+    // - (a_1 * e_1) * P_1 - ... - (a_u * e_u) * P_u is the neutral element.
+    let mut terms = Vec::with_capacity(2 * batch.len() + 1);
+    let mut s_sum = scalar::Scalar::ZERO;
+
+    for (i, (public_key, message, (r, s))) in batch.iter().enumerate() {
+        if *public_key >= PRIME || *r >= PRIME || *s >= GROUP_ORDER {
+            return false;
+        }
+
+        let Ok(public_point) = lift_x(*public_key) else {
+            return false;
+        };
+
+        let Ok(nonce_point) = lift_x(*r) else {
+            return false;
+        };
+
+        let a = if i == 0 {
+            scalar::Scalar::ONE
+        } else {
+            let mut hasher = hash::Sha256::empty();
+            hasher.write(&seed);
+            hasher.write(&(i as u64).to_be_bytes());
+            scalar::Scalar::decode(hasher.finish())
+        };
+
+        let e = scalar::Scalar::decode(hash(r, public_key, message));
+
+        s_sum = s_sum + a * scalar::Scalar::decode(*s);
+
+        terms.push((nonce_point, (-a).encode()));
+        terms.push((public_point, (-(a * e)).encode()));
+    }
+
+    terms.push((group::AffinePoint::GENERATOR, s_sum.encode()));
+
+    group_multiplication::multiply_multi(&terms).is_neutral()
 }
 
 #[cfg(test)]
@@ -182,7 +320,184 @@ mod tests {
         "897EFCB639EA871CFA95F6DE339E4B0A"
         );
 
-        let result = super::verify_signature(public_key, message, (signature_r, signature_s));
+        let result = super::verify_signature(public_key, &message, (signature_r, signature_s));
+
+        assert!(result.is_ok());
+    }
+
+    fn decode_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn expected_error(index: usize) -> super::VerificationError {
+        match index {
+            5 => super::VerificationError::FailedToSolve,
+            6 => super::VerificationError::IsOdd,
+            7 => super::VerificationError::NotEqual,
+            8 => super::VerificationError::NotEqual,
+            9 => super::VerificationError::IsNeutral,
+            10 => super::VerificationError::IsNeutral,
+            11 => super::VerificationError::NotEqual,
+            12 => super::VerificationError::SignatureOutOfBounds,
+            13 => super::VerificationError::SignatureOutOfBounds,
+            14 => super::VerificationError::PublicKeyOutOfBounds,
+            _ => panic!("test vector {} is expected to verify", index),
+        }
+    }
+
+    /// Run all test vectors from the BIP340 test-vectors.csv
+    struct TestVector {
+        index: usize,
+        secret_key: Option<[u8; 32]>,
+        public_key: [u8; 32],
+        aux_rand: Option<[u8; 32]>,
+        message: Vec<u8>,
+        signature: ([u8; 32], [u8; 32]),
+        result: bool,
+    }
+
+    fn test_vectors() -> Vec<TestVector> {
+        let vectors = include_str!("bip340_test_vectors.csv");
+
+        vectors
+            .lines()
+            .skip(1)
+            .map(|line| {
+                let columns: Vec<&str> = line.splitn(8, ',').collect();
+
+                let index: usize = columns[0].parse().unwrap();
+                let signature = decode_hex(columns[5]);
+
+                let (secret_key, aux_rand) = if columns[1].is_empty() {
+                    (None, None)
+                } else {
+                    (
+                        Some(decode_hex(columns[1]).try_into().unwrap()),
+                        Some(decode_hex(columns[3]).try_into().unwrap()),
+                    )
+                };
+
+                let result = match columns[6] {
+                    "TRUE" => true,
+                    "FALSE" => false,
+                    _ => panic!("invalid verification result for test vector {}", index),
+                };
+
+                TestVector {
+                    index,
+                    secret_key,
+                    public_key: decode_hex(columns[2]).try_into().unwrap(),
+                    aux_rand,
+                    message: decode_hex(columns[4]),
+                    signature: (
+                        signature[..32].try_into().unwrap(),
+                        signature[32..].try_into().unwrap(),
+                    ),
+                    result,
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn bip340_test_vectors() {
+        for vector in test_vectors() {
+            let index = vector.index;
+
+            if let (Some(secret_key), Some(aux_rand)) = (vector.secret_key, vector.aux_rand) {
+                assert!(
+                    super::public_key(secret_key) == vector.public_key,
+                    "wrong public key for test vector {}",
+                    index
+                );
+
+                assert!(
+                    super::sign(secret_key, &vector.message, aux_rand) == vector.signature,
+                    "wrong signature for test vector {}",
+                    index
+                );
+            }
+
+            let result =
+                super::verify_signature(vector.public_key, &vector.message, vector.signature);
+
+            if vector.result {
+                assert!(
+                    result.is_ok(),
+                    "test vector {} failed with {:?}",
+                    index,
+                    result
+                );
+            } else {
+                assert!(
+                    result == Err(expected_error(index)),
+                    "test vector {} failed with {:?}",
+                    index,
+                    result
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn verify_batch() {
+        let (valid, invalid): (Vec<TestVector>, Vec<TestVector>) =
+            test_vectors().into_iter().partition(|vector| vector.result);
+
+        let batch: Vec<super::BatchEntry> = valid
+            .iter()
+            .map(|vector| (vector.public_key, &vector.message[..], vector.signature))
+            .collect();
+
+        assert!(super::verify_batch(&[]).is_ok());
+        assert!(super::verify_batch(&batch).is_ok());
+
+        for vector in &invalid {
+            let expected =
+                super::verify_signature(vector.public_key, &vector.message, vector.signature);
+
+            let mut batch = batch.clone();
+            batch.insert(
+                3,
+                (vector.public_key, &vector.message[..], vector.signature),
+            );
+
+            assert!(super::verify_batch(&batch) == Err((3, expected.unwrap_err())));
+        }
+    }
+
+    #[test]
+    fn sign() {
+        let secret_key: [u8; 32] = hex_literal::hex!(
+        "C90FDAA22168C234C4C6628B80DC1CD1"
+        "29024E088A67CC74020BBEA63B14E5C9"
+        );
+        let aux_rand: [u8; 32] = hex_literal::hex!(
+        "C87AA53824B4D7AE2EB035A2B5BBBCCC"
+        "080E76CDC6D1692C4B0B62D798E6D906"
+        );
+        let message: [u8; 32] = hex_literal::hex!(
+        "7E2D58D8B3BCDF1ABADEC7829054F90D"
+        "DA9805AAB56C77333024B9D0A508B75C"
+        );
+        let signature_r: [u8; 32] = hex_literal::hex!(
+        "5831AAEED7B44BB74E5EAB94BA9D4294"
+        "C49BCF2A60728D8B4C200F50DD313C1B"
+        );
+        let signature_s: [u8; 32] = hex_literal::hex!(
+        "AB745879A5AD954A72C45A91C3A51D3C"
+        "7ADEA98D82F8481E0E1E03674A6F3FB7"
+        );
+
+        let signature = super::sign(secret_key, &message, aux_rand);
+
+        assert!(signature == (signature_r, signature_s));
+
+        let public_key = super::public_key(secret_key);
+        let result = super::verify_signature(public_key, &message, signature);
 
         assert!(result.is_ok());
     }